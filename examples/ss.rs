@@ -6,30 +6,33 @@ use fermentation::g::Exponential;
 use fermentation::space_saving::BTreeSpaceSaving;
 
 fn main() {
-    let path = args().skip(1).next().expect("must pass an input file");
+    let path = args().nth(1).expect("must pass an input file");
     let contents = read_to_string(path.as_str()).expect("unable to open file");
     let start = Instant::now();
     let decay = ForwardDecay::new(start, Exponential::rate(0.001, Duration::from_secs(60)));
     let mut ss = BTreeSpaceSaving::new(8, decay);
 
-    let mut hits = 0;
+    // Counts each word as one tick instead of reading the clock, so the decayed counts are reproducible.
+    if args().nth(2).as_deref() == Some("--ticks") {
+        ss.set_ticks(1);
+    }
 
     for e in contents.split_whitespace() {
-        hits += 1;
         ss.hit(e);
     }
 
     let top = ss.top(2).expect("unable to guarantee top hitters");
     let frequent = ss.frequent(0.1);
     let end = Instant::now();
+    let now = ss.now();
 
     println!("Elapsed: {}", (end - start).as_secs_f64());
     println!("Top elements: {:?}", &top);
 
     for (index, e) in top.into_iter().enumerate() {
-        println!("Element {index} is {} with {:?}", e, ss.get(e, end));
+        println!("Element {index} is {} with {:?}", e, ss.get(e, now));
     }
 
     println!("Frequent elements: {:?}", frequent);
-    println!("Total hits: {}, Decayed hits: {}", hits, ss.hits(end));
+    println!("Total hits: {}, Decayed hits: {}", ss.total_observed(), ss.hits(now));
 }
@@ -0,0 +1,51 @@
+use std::env::args;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::{Duration, Instant};
+use fermentation::ForwardDecay;
+use fermentation::g::Exponential;
+use fermentation::space_saving::BTreeSpaceSaving;
+
+const SAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/ss_stream.txt");
+
+/// Streams whitespace-separated elements from a file line by line, without reading the whole file into memory.
+/// A line may start with a timestamp in milliseconds since the start of the stream, which is used for every hit on that line;
+/// otherwise, hits are timestamped when they are read.
+/// Defaults to a small sample file when no path is given.
+fn main() {
+    let path = args().nth(1);
+    let file = File::open(path.as_deref().unwrap_or(SAMPLE)).expect("unable to open file");
+    let start = Instant::now();
+    let decay = ForwardDecay::new(start, Exponential::rate(0.001, Duration::from_secs(60)));
+    let mut ss = BTreeSpaceSaving::new(8, decay);
+    let mut latest = start;
+
+    for line in BufReader::new(file).lines() {
+        let line = line.expect("unable to read line");
+        let mut words = line.split_whitespace().peekable();
+        let timestamp = words.next_if(|word| word.parse::<u64>().is_ok())
+            .map(|millis| start + Duration::from_millis(millis.parse().unwrap()));
+
+        for word in words {
+            let timestamp = timestamp.unwrap_or_else(Instant::now);
+
+            latest = latest.max(timestamp);
+            ss.hit_at(word.to_string(), timestamp);
+        }
+    }
+
+    let top = ss.top(2).expect("unable to guarantee top hitters");
+
+    println!("Top elements: {:?}", &top);
+
+    for (index, e) in top.iter().enumerate() {
+        println!("Element {index} is {} with {:?}", e, ss.get(e, latest));
+    }
+
+    println!("Total hits: {}, Decayed hits: {}", ss.total_observed(), ss.hits(latest));
+
+    if path.is_none() {
+        assert_eq!(ss.total_observed(), 32);
+        assert_eq!(top, vec!["GET", "/index"]);
+    }
+}
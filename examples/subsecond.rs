@@ -0,0 +1,28 @@
+use std::time::{Duration, Instant};
+use fermentation::ForwardDecay;
+use fermentation::aggregate::{Aggregator, BasicAggregator};
+use fermentation::g::Exponential;
+
+/// Simulates a stream of latency samples arriving every 250 microseconds,
+/// decayed with a half-life of 10 milliseconds.
+fn main() {
+    let landmark = Instant::now();
+    let interval = Duration::from_micros(250);
+    let half_life = Duration::from_millis(10);
+    let decay = ForwardDecay::new(landmark, Exponential::rate(0.5, half_life));
+    let mut aggregator = BasicAggregator::new(decay);
+
+    for index in 1..=200u32 {
+        let timestamp = landmark + interval * index;
+        let latency = if index > 160 { 5.0 } else { 1.0 };
+
+        aggregator.update((timestamp, latency));
+    }
+
+    let end = landmark + interval * 200;
+
+    println!("Elapsed: {:?}", end - landmark);
+    println!("Decayed sum: {}", aggregator.sum(end));
+    println!("Decayed count: {}", aggregator.count(end));
+    println!("Decayed average: {}", aggregator.average());
+}
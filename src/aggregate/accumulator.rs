@@ -0,0 +1,107 @@
+/// A strategy for summing the static weighted terms of an aggregation.
+/// Aggregators are parameterized over the strategy, so the trade-off between speed and accuracy is chosen per aggregator.
+pub trait Accumulator: Default {
+    /// Adds a term to the total.
+    fn add(&mut self, term: f64);
+
+    /// The total of every term added so far.
+    fn total(&self) -> f64;
+
+    /// Multiplies the total by the given factor, i.e. when rescaling to a new landmark.
+    fn scale(&mut self, factor: f64);
+}
+
+/// Plain floating-point summation.
+/// The rounding error grows with the number of terms, and small terms are lost entirely when added to a much larger total.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Naive(f64);
+
+impl Accumulator for Naive {
+    fn add(&mut self, term: f64) {
+        self.0 += term;
+    }
+
+    fn total(&self) -> f64 {
+        self.0
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.0 *= factor;
+    }
+}
+
+/// Compensated summation, using the Kahan-Babuška (Neumaier) variant of [Kahan summation](https://en.wikipedia.org/wiki/Kahan_summation_algorithm).
+/// A running compensation captures the low-order bits lost by each addition,
+/// so the error stays bounded independent of the number of terms, at the cost of a few extra operations per term.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Kahan {
+    sum: f64,
+    compensation: f64,
+}
+
+impl Accumulator for Kahan {
+    fn add(&mut self, term: f64) {
+        let sum = self.sum + term;
+
+        if self.sum.abs() >= term.abs() {
+            self.compensation += (self.sum - sum) + term;
+        } else {
+            self.compensation += (term - sum) + self.sum;
+        }
+
+        self.sum = sum;
+    }
+
+    fn total(&self) -> f64 {
+        self.sum + self.compensation
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.sum *= factor;
+        self.compensation *= factor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum<A: Accumulator>(terms: impl IntoIterator<Item = f64>) -> f64 {
+        let mut accumulator = A::default();
+
+        for term in terms {
+            accumulator.add(term);
+        }
+
+        accumulator.total()
+    }
+
+    #[test]
+    fn compensated() {
+        let terms = || std::iter::once(1.0).chain(std::iter::repeat_n(1e-16, 1_000_000));
+        let expected = 1.0 + 1e-10;
+
+        let naive = (sum::<Naive>(terms()) - expected).abs();
+        let kahan = (sum::<Kahan>(terms()) - expected).abs();
+
+        assert!(kahan < naive);
+        assert!(kahan < 1e-15);
+    }
+
+    #[test]
+    fn scale() {
+        let mut naive = Naive::default();
+        let mut kahan = Kahan::default();
+
+        for term in [1.0, 2.0, 3.0] {
+            naive.add(term);
+            kahan.add(term);
+        }
+
+        naive.scale(0.5);
+        kahan.scale(0.5);
+
+        assert_eq!(naive.total(), 3.0);
+        assert_eq!(kahan.total(), 3.0);
+    }
+}
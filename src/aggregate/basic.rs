@@ -1,11 +1,190 @@
+use std::collections::HashMap;
+use std::hash::Hash;
 use std::marker::PhantomData;
+use std::sync::mpsc::Receiver;
 use std::time::Instant;
-use crate::{ForwardDecay, Item};
-use crate::aggregate::Aggregator;
+use crate::{Error, ForwardDecay, Item, WeightedItem};
+use crate::aggregate::{Accumulator, Aggregator, Checkpoint, CheckpointData, DedupAggregator, Naive, Normalization, QueryContext};
 use crate::g::{Exponential, Function};
+use crate::landmark::{ClockAnchor, SyncLandmark};
+
+/// How an aggregator handles items that arrived before its landmark.
+///
+/// Weights of pre-landmark items are meaningless for most decay functions;
+/// i.e. a polynomial function of even degree gives them a positive weight as if they had arrived after the landmark.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PreLandmarkPolicy {
+    /// Ignore the item entirely.
+    #[default]
+    Drop,
+    /// Treat the item as if it had arrived exactly at the landmark.
+    /// Note that the weight at the landmark is g(0), which is 0 for polynomial decay.
+    Clamp,
+    /// Reject the item with an [Error::PreLandmark].
+    /// Updates that cannot return the error skip the item and count it as rejected instead.
+    Error,
+}
+
+/// An item whose timestamp went backwards, as reported to the callback set by [BasicAggregator::set_warn_on_backwards_time].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BackwardsTime {
+    /// The item arrived before the landmark.
+    BeforeLandmark { timestamp: Instant, landmark: Instant },
+    /// The item arrived before the newest item aggregated so far.
+    BeforeLastUpdate { timestamp: Instant, last_update: Instant },
+}
+
+/// Counts of the items seen by a [BasicAggregator] since creation or the last reset, by how they were handled.
+/// Useful for debugging unexpectedly low sums as guards against bad items accumulate.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Items aggregated into the sum and count.
+    pub processed: u64,
+    /// Items skipped because their value was NaN.
+    pub skipped_nan: u64,
+    /// Items dropped for arriving before the landmark under [PreLandmarkPolicy::Drop].
+    pub dropped_pre_landmark: u64,
+    /// Items rejected for arriving before the landmark under [PreLandmarkPolicy::Error],
+    /// by an update that has no way to return the error, i.e. [Aggregator::update].
+    pub rejected_pre_landmark: u64,
+    /// Items skipped for contributing less than the minimum. See [BasicAggregator::set_min_contribution].
+    pub skipped_negligible: u64,
+    /// Items rejected for arriving out of order. See [BasicAggregator::set_strict_ordering].
+    pub out_of_order: u64,
+}
+
+/// The decayed aggregates of a [BasicAggregator] at a point in time. See [BasicAggregator::snapshot].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub sum: f64,
+    pub count: f64,
+    pub average: f64,
+}
+
+/// The raw decayed state of a [BasicAggregator], for custom merging or serialization.
+/// The sum and count are static (not normalized), relative to the landmark.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AggregatorState {
+    pub sum: f64,
+    pub count: f64,
+    pub landmark: Instant,
+}
+
+impl AggregatorState {
+    /// Converts the landmark of a state computed in a process with the given anchor to the instant of another anchor,
+    /// i.e. of the process that merges it. See [ClockAnchor] for the clock-skew assumptions.
+    /// The static aggregates are relative to the landmark, so they carry over unchanged.
+    ///
+    /// ## Panic
+    /// Panics when the landmark precedes the origin of the monotonic clock in the target process.
+    pub fn rebase(self, from: &ClockAnchor, to: &ClockAnchor) -> Self {
+        Self {
+            landmark: to.to_instant(from.to_system_time(self.landmark)),
+            ..self
+        }
+    }
+}
+
+/// Configures the options of a [BasicAggregator] before building it.
+///
+/// ## Example
+/// ```rust
+/// use std::time::Instant;
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{BasicAggregator, PreLandmarkPolicy};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let aggregator: BasicAggregator<_, (Instant, f64)> = BasicAggregator::builder(decay)
+///     .policy(PreLandmarkPolicy::Clamp)
+///     .strict_ordering(true)
+///     .build();
+///
+/// assert_eq!(aggregator.policy(), PreLandmarkPolicy::Clamp);
+/// ```
+#[derive(Copy, Clone)]
+pub struct BasicAggregatorBuilder<G, I, A = Naive> {
+    aggregator: BasicAggregator<G, I, A>,
+}
+
+impl<G, I, A> BasicAggregatorBuilder<G, I, A>
+where
+    G: Function,
+    I: Item,
+    A: Accumulator,
+{
+    /// The policy for items that arrived before the landmark.
+    pub fn policy(mut self, policy: PreLandmarkPolicy) -> Self {
+        self.aggregator.policy = policy;
+        self
+    }
+
+    /// Whether to reject items that arrive earlier than the newest timestamp seen so far.
+    /// See [BasicAggregator::set_strict_ordering].
+    pub fn strict_ordering(mut self, strict: bool) -> Self {
+        self.aggregator.strict = strict;
+        self
+    }
+
+    /// The maximum decayed sum returned by queries. See [BasicAggregator::set_ceiling].
+    pub fn ceiling(mut self, ceiling: f64) -> Self {
+        self.aggregator.ceiling = Some(ceiling);
+        self
+    }
+
+    /// Whether the first aggregated item replaces the landmark with its own timestamp.
+    /// See [BasicAggregator::set_anchor_to_first_item].
+    pub fn anchor_to_first_item(mut self, anchor: bool) -> Self {
+        self.aggregator.anchor = anchor;
+        self
+    }
+
+    /// The minimum static weight for an item to be aggregated. See [BasicAggregator::set_min_contribution].
+    pub fn min_contribution(mut self, min_contribution: f64) -> Self {
+        self.aggregator.min_contribution = min_contribution;
+        self
+    }
+
+    /// The callback for items whose timestamp went backwards. See [BasicAggregator::set_warn_on_backwards_time].
+    pub fn warn_on_backwards_time(mut self, callback: fn(BackwardsTime)) -> Self {
+        self.aggregator.warn = Some(callback);
+        self
+    }
+
+    /// The summation strategy for the sum and count.
+    pub fn accumulator<B>(self) -> BasicAggregatorBuilder<G, I, B>
+    where
+        B: Accumulator,
+    {
+        BasicAggregatorBuilder {
+            aggregator: self.aggregator.with_accumulators(),
+        }
+    }
+
+    pub fn build(self) -> BasicAggregator<G, I, A> {
+        self.aggregator
+    }
+}
 
 /// Decayed aggregate sum, count and average over a stream of items.
 ///
+/// Items whose value is NaN (i.e. a bare [Instant]) are skipped, since a single NaN would otherwise poison the sum.
+/// The number of skipped items is available via [BasicAggregator::skipped].
+/// Items that arrived before the landmark are handled according to the aggregator's [PreLandmarkPolicy].
+/// The static weight of each item is multiplied by its [WeightedItem::importance].
+/// The sum and count use [Naive] summation unless another [Accumulator] is chosen, i.e. [crate::aggregate::Kahan].
+///
+/// ## Exactness
+/// Floating-point addition is not associative, so in general the sums depend on the order of the items in the last few bits.
+/// The examples assert exact values because their static weights are whole numbers:
+/// under polynomial decay with whole-second ages, every static weight and static weighted value is an integer,
+/// which floating-point arithmetic sums exactly in any order, leaving a single correctly rounded division at query time.
+/// Elsewhere, compare results within a tolerance, or use [crate::aggregate::Kahan] summation to reduce the order dependence.
+///
+/// ## Errors
+/// Under [PreLandmarkPolicy::Error], updates that cannot return an error, such as [Aggregator::update] or [Extend],
+/// skip a pre-landmark item and count it as rejected (see [BasicAggregator::rejected]);
+/// use [BasicAggregator::try_update] or [BasicAggregator::update_results] to handle the error instead.
+///
 /// ## Examples
 /// ### Basic Aggregation
 /// ```rust
@@ -74,40 +253,245 @@ use crate::g::{Exponential, Function};
 /// assert!((aggregator.average() - clone.average()).abs() < epsilon);
 /// ```
 #[derive(Copy, Clone)]
-pub struct BasicAggregator<G, I> {
+pub struct BasicAggregator<G, I, A = Naive> {
     decay: ForwardDecay<G>,
-    sum: f64,
-    count: f64,
+    sum: A,
+    count: A,
+    processed: u64,
+    skipped: u64,
+    last_update: Option<Instant>,
+    last_value: Option<f64>,
+    strict: bool,
+    out_of_order: u64,
+    policy: PreLandmarkPolicy,
+    dropped: u64,
+    rejected: u64,
+    ceiling: Option<f64>,
+    anchor: bool,
+    min_contribution: f64,
+    negligible: u64,
+    warn: Option<fn(BackwardsTime)>,
+    pending: Option<Instant>,
     _phantom_data: PhantomData<I>
 }
 
-impl<G, I> Aggregator for BasicAggregator<G, I> where G: Function, I: Item {
+impl<G, I, A> Aggregator for BasicAggregator<G, I, A> where G: Function, I: WeightedItem, A: Accumulator {
     type Item = I;
+    /// The decayed sum.
+    type Output = f64;
 
     fn update(&mut self, item: I) {
-        let static_weight = self.decay.static_weight(&item);
-
-        self.sum += static_weight * item.value();
-        self.count += static_weight;
+        self.add(item);
     }
 
     fn reset(&mut self, landmark: Instant) {
         self.decay.set_landmark(landmark);
-        self.sum = 0.0;
-        self.count = 0.0;
+        self.sum = A::default();
+        self.count = A::default();
+        self.processed = 0;
+        self.skipped = 0;
+        self.last_update = None;
+        self.last_value = None;
+        self.out_of_order = 0;
+        self.dropped = 0;
+        self.rejected = 0;
+        self.negligible = 0;
+        self.pending = None;
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.sum(timestamp)
+    }
+}
+
+impl<G, I, A> Extend<I> for BasicAggregator<G, I, A> where G: Function, I: WeightedItem, A: Accumulator {
+    fn extend<T: IntoIterator<Item = I>>(&mut self, items: T) {
+        for item in items {
+            self.update(item);
+        }
+    }
+}
+
+impl<G, I, A> BasicAggregator<G, I, A>
+where
+    G: Function,
+    I: WeightedItem,
+    A: Accumulator,
+{
+    /// Update the aggregation with the given item.
+    /// Returns an error, without updating the aggregation,
+    /// when the item arrived before the landmark under [PreLandmarkPolicy::Error].
+    pub fn try_update(&mut self, item: I) -> Result<(), Error> {
+        self.try_add(item)
+    }
+
+    /// Updates the aggregation with every successful item, returning the errors for the caller to handle,
+    /// both those of the given items and those of updating with them, i.e. [Error::PreLandmark].
+    pub fn update_results<E>(&mut self, items: impl IntoIterator<Item = Result<I, E>>) -> Vec<E>
+    where
+        E: From<Error>,
+    {
+        let mut errors = Vec::new();
+
+        for item in items {
+            match item.and_then(|item| Ok(self.try_add(item)?)) {
+                Ok(()) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+
+        errors
+    }
+
+    /// Resets the aggregation with the given time as the landmark when the predicate over the current state returns true,
+    /// i.e. after a regime change is detected. Returns whether the aggregation was reset.
+    pub fn maybe_reset(&mut self, timestamp: Instant, predicate: impl Fn(&Self, Instant) -> bool) -> bool {
+        let reset = predicate(self, timestamp);
+
+        if reset {
+            self.reset(timestamp);
+        }
+
+        reset
+    }
+
+    /// Resets the aggregation with the given landmark, like [Aggregator::reset], then re-seeds it with the last value,
+    /// as if an item with that value arrived at the new landmark. Gauge-style metrics thus carry their last value
+    /// across a window reset instead of dropping to zero: the average equals the last value until new items arrive.
+    ///
+    /// The seed has a static weight of 1, the weight of an item at the landmark under exponential decay,
+    /// since functions that vanish at the landmark, such as polynomial decay, would otherwise give it no weight.
+    /// Like [BasicAggregator::update_raw], the seed is not counted as a processed item and does not set the last update time.
+    /// Without a last value, this is equivalent to [Aggregator::reset].
+    pub fn reset_preserving_last(&mut self, landmark: Instant) {
+        let last_value = self.last_value;
+
+        self.reset(landmark);
+
+        if let Some(value) = last_value {
+            self.sum.add(value);
+            self.count.add(1.0);
+            self.last_value = Some(value);
+        }
+    }
+
+    /// Updates the aggregation with every item currently available on the channel, without blocking.
+    /// Returns the number of items received.
+    pub fn drain_channel(&mut self, receiver: &Receiver<I>) -> usize {
+        let mut received = 0;
+
+        for item in receiver.try_iter() {
+            self.add(item);
+            received += 1;
+        }
+
+        received
+    }
+
+    /// Updates the aggregation with every item on the channel, blocking until all senders are dropped.
+    /// Returns the number of items received.
+    pub fn drain_channel_until_closed(&mut self, receiver: &Receiver<I>) -> usize {
+        let mut received = 0;
+
+        for item in receiver.iter() {
+            self.add(item);
+            received += 1;
+        }
+
+        received
+    }
+
+    /// Updates the aggregation with the given item, then returns the decayed sum at the item's timestamp.
+    pub fn update_and_query(&mut self, item: I) -> f64 {
+        let timestamp = item.timestamp();
+
+        self.update(item);
+        self.sum(timestamp)
     }
 }
 
-impl<I> BasicAggregator<Exponential, I>
+impl<I, A> BasicAggregator<Exponential, I, A>
 where
     I: Item,
+    A: Accumulator,
 {
+    /// Moves the landmark, rescaling the accumulators so that queries are unaffected.
+    /// Supersedes any landmark change deferred with [BasicAggregator::defer_landmark].
     pub fn update_landmark(&mut self, landmark: Instant) {
         let age = self.decay.set_landmark(landmark);
         let factor = self.decay.g().invoke(age);
 
-        self.sum /= factor;
-        self.count /= factor;
+        self.pending = None;
+        self.sum.scale(1.0 / factor);
+        self.count.scale(1.0 / factor);
+    }
+
+    /// Defers moving the landmark until [BasicAggregator::flush_landmark] is called,
+    /// so that a burst of landmark changes is coalesced into a single rescale by the net change.
+    /// Each rescale divides the accumulators, compounding rounding error, whereas g(a) × g(b) = g(a + b) under exponential decay.
+    ///
+    /// The coalescing window lasts from the first deferred change until the next call to
+    /// [BasicAggregator::flush_landmark], [BasicAggregator::update_landmark] or [Aggregator::reset];
+    /// only the last deferred landmark is kept.
+    /// Exponential decay does not depend on the landmark, so queries are unaffected while a change is pending,
+    /// and items aggregated in the meantime are weighted relative to the current landmark and rescaled along with the rest.
+    pub fn defer_landmark(&mut self, landmark: Instant) {
+        self.pending = Some(landmark);
+    }
+
+    /// Applies the last landmark change deferred with [BasicAggregator::defer_landmark], if any.
+    /// Returns whether the landmark moved.
+    pub fn flush_landmark(&mut self) -> bool {
+        match self.pending.take() {
+            Some(landmark) => {
+                self.update_landmark(landmark);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The landmark deferred with [BasicAggregator::defer_landmark] that has yet to be applied.
+    pub fn pending_landmark(&self) -> Option<Instant> {
+        self.pending
+    }
+}
+
+impl<I, A> SyncLandmark for BasicAggregator<Exponential, I, A>
+where
+    I: Item,
+    A: Accumulator,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<I, A> BasicAggregator<(), I, A>
+where
+    I: Item,
+    A: Accumulator,
+{
+    /// The count as an integer, for aggregators without decay.
+    /// Every item has a static weight of 1 without decay, so the count is a whole number stored exactly
+    /// up to 2<sup>53</sup> items, beyond which a floating-point count can no longer represent every integer.
+    ///
+    /// Returns [None] when the count is not an exactly representable whole number,
+    /// i.e. when items have an importance other than 1 or after [BasicAggregator::update_raw].
+    pub fn exact_count(&self) -> Option<u64> {
+        const MAX_EXACT: f64 = (1u64 << f64::MANTISSA_DIGITS) as f64;
+
+        let count = self.count.total();
+
+        if count.fract() == 0.0 && (0.0..=MAX_EXACT).contains(&count) {
+            Some(count as u64)
+        } else {
+            None
+        }
     }
 }
 
@@ -117,71 +501,2003 @@ where
     I: Item,
 {
     pub fn new(decay: ForwardDecay<G>) -> Self {
+        Self::with_policy(decay, PreLandmarkPolicy::default())
+    }
+
+    /// A builder for an aggregator with non-default options.
+    pub fn builder(decay: ForwardDecay<G>) -> BasicAggregatorBuilder<G, I> {
+        BasicAggregatorBuilder {
+            aggregator: Self::new(decay),
+        }
+    }
+
+    /// Initializes a new aggregator that handles pre-landmark items according to the given policy.
+    pub fn with_policy(decay: ForwardDecay<G>, policy: PreLandmarkPolicy) -> Self {
+        let mut aggregator = Self::with_accumulator(decay);
+
+        aggregator.policy = policy;
+        aggregator
+    }
+
+    /// Skips duplicate deliveries of the same item, as identified by the given key extractor,
+    /// remembering the keys of up to the given number of recent items. See [DedupAggregator].
+    ///
+    /// ## Panic
+    /// Panics when the capacity is 0.
+    pub fn dedup_by<K, F>(self, capacity: usize, id: F) -> DedupAggregator<G, I, K, F>
+    where
+        K: Eq + Hash,
+        F: Fn(&I) -> K,
+    {
+        DedupAggregator::new(self, capacity, id)
+    }
+}
+
+impl<G, I, A> BasicAggregator<G, I, A>
+where
+    G: Function,
+    I: Item,
+    A: Accumulator,
+{
+    /// Initializes a new aggregator that sums with the given [Accumulator],
+    /// i.e. `BasicAggregator::<_, _, Kahan>::with_accumulator(decay)` for compensated summation.
+    pub fn with_accumulator(decay: ForwardDecay<G>) -> Self {
         Self {
             decay,
-            sum: 0.0,
-            count: 0.0,
+            sum: A::default(),
+            count: A::default(),
+            processed: 0,
+            skipped: 0,
+            last_update: None,
+            last_value: None,
+            strict: false,
+            out_of_order: 0,
+            policy: PreLandmarkPolicy::default(),
+            dropped: 0,
+            rejected: 0,
+            ceiling: None,
+            anchor: false,
+            min_contribution: 0.0,
+            negligible: 0,
+            warn: None,
+            pending: None,
             _phantom_data: Default::default()
         }
     }
 
+    /// Rebuilds an aggregator directly from its static sum and count, relative to the landmark of the given decay model,
+    /// i.e. as read back from a custom serialization of [BasicAggregator::static_state].
+    /// Unlike [BasicAggregator::from_state], the landmark is taken from the decay model as is.
+    ///
+    /// Only the accumulators are restored: the statistics, the last update and the options start out as for a new aggregator.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use fermentation::{ForwardDecay, g};
+    /// use fermentation::aggregate::{Aggregator, BasicAggregator};
+    ///
+    /// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(1));
+    /// let landmark = decay.landmark();
+    /// let aggregator: BasicAggregator<_, (Instant, f64)> = BasicAggregator::from_parts(decay, 10.0, 5.0);
+    ///
+    /// assert_eq!(aggregator.sum(landmark + Duration::from_secs(10)), 1.0);
+    /// assert_eq!(aggregator.average(), 2.0);
+    /// ```
+    pub fn from_parts(decay: ForwardDecay<G>, sum: f64, count: f64) -> Self {
+        let mut aggregator = Self::with_accumulator(decay);
+
+        aggregator.sum.add(sum);
+        aggregator.count.add(count);
+        aggregator
+    }
+
+    /// Rebuilds an aggregator from the [BasicAggregator::static_state] of another, using the landmark of the state.
+    pub fn from_state(mut decay: ForwardDecay<G>, state: AggregatorState) -> Self {
+        decay.set_landmark(state.landmark);
+
+        Self::from_parts(decay, state.sum, state.count)
+    }
+
+    /// The raw static accumulators of this aggregator, before normalization.
+    pub fn static_state(&self) -> AggregatorState {
+        AggregatorState {
+            sum: self.sum.total(),
+            count: self.count.total(),
+            landmark: self.decay.landmark(),
+        }
+    }
+
+    /// Combines the aggregates of another aggregator into this one, as if this aggregator had seen the other's items as well.
+    /// Returns an error, without changing this aggregator, when the landmarks differ,
+    /// since the static aggregates are only comparable relative to the same landmark,
+    /// or when the decay functions or time scales differ, i.e. exponential decay with different rates.
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error>
+    where
+        G: PartialEq,
+    {
+        if self.decay.landmark() != other.decay.landmark() {
+            return Err(Error::LandmarkMismatch);
+        }
+
+        if self.decay.g() != other.decay.g() || self.decay.time_scale() != other.decay.time_scale() {
+            return Err(Error::DecayMismatch);
+        }
+
+        self.sum.add(other.sum.total());
+        self.count.add(other.count.total());
+        self.processed += other.processed;
+        self.skipped += other.skipped;
+        self.out_of_order += other.out_of_order;
+        self.dropped += other.dropped;
+        self.rejected += other.rejected;
+        self.negligible += other.negligible;
+
+        if other.last_update > self.last_update {
+            self.last_update = other.last_update;
+            self.last_value = other.last_value;
+        }
+
+        Ok(())
+    }
+
+    /// Consumes both aggregators and returns the combined aggregator, avoiding a clone in fold or reduce pipelines.
+    ///
+    /// ## Panic
+    /// Panics when the landmarks or decay functions differ. See [BasicAggregator::merge].
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use fermentation::ForwardDecay;
+    /// use fermentation::aggregate::{BasicAggregator, Aggregator};
+    ///
+    /// let landmark = Instant::now();
+    /// let mut left = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+    /// let mut right = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+    ///
+    /// left.update((landmark, 1.0));
+    /// right.update((landmark, 2.0));
+    ///
+    /// assert_eq!(left.merge_into(right).sum(landmark), 3.0);
+    /// ```
+    pub fn merge_into(mut self, other: Self) -> Self
+    where
+        G: PartialEq,
+    {
+        if let Err(error) = self.merge(&other) {
+            panic!("{error}");
+        }
+
+        self
+    }
+
+    /// Updates the aggregation with the given value at the current time.
+    /// An alias for updating with an `(Instant::now(), value)` item, following common metrics conventions.
+    pub fn observe(&mut self, value: f64) {
+        self.add((Instant::now(), value));
+    }
+
+    /// Updates the aggregation with a value and a pre-computed static weight, relative to the landmark,
+    /// bypassing the decay function entirely. An escape hatch for custom decay kernels computed elsewhere.
+    ///
+    /// None of the per-item options apply: the value is not checked for NaN, the pre-landmark policy,
+    /// strict ordering and minimum contribution are ignored, and the last update time is unchanged.
+    pub fn update_raw(&mut self, weight: f64, value: f64) {
+        self.sum.add(weight * value);
+        self.count.add(weight);
+    }
+
+    /// Updates the aggregation with a batch of values that all arrived at the same time.
+    /// The static weight is computed once and shared by every value in the batch.
+    pub fn update_batch_same_time(&mut self, timestamp: Instant, values: &[f64]) {
+        if timestamp < self.decay.landmark() {
+            for value in values {
+                self.add((timestamp, *value));
+            }
+
+            return;
+        }
+
+        let mut static_weight = None;
+
+        for value in values {
+            if self.admit(timestamp, *value) {
+                let static_weight = *static_weight.get_or_insert_with(|| self.decay.static_weight(timestamp));
+
+                self.contribute(timestamp, static_weight, *value);
+            }
+        }
+    }
+
+    /// Updates the aggregation with columnar data, treating the timestamp and value at each index as a (timestamp, value) item.
+    /// The columns are folded in a single pass, without zipping them into a collection of items first.
+    ///
+    /// ## Panic
+    /// Panics when the columns have different lengths, before updating the aggregation.
+    pub fn observe_many(&mut self, timestamps: &[Instant], values: &[f64]) {
+        if timestamps.len() != values.len() {
+            panic!("expected as many values as timestamps, given {} timestamps and {} values", timestamps.len(), values.len());
+        }
+
+        for (timestamp, value) in timestamps.iter().zip(values) {
+            self.add((*timestamp, *value));
+        }
+    }
+
+    /// Updates the aggregation with each entry of the map, treating the entry as a (timestamp, value) item.
+    pub fn update_buckets(&mut self, buckets: &HashMap<Instant, f64>) {
+        for (timestamp, value) in buckets {
+            self.add((*timestamp, *value));
+        }
+    }
+
+    /// The decayed sum, saturated at the ceiling if one is set.
+    ///
+    /// Queries are marked `#[must_use]`, so discarding the result is flagged by the compiler:
+    /// ```rust,compile_fail
+    /// #![deny(unused_must_use)]
+    /// use std::time::Instant;
+    /// use fermentation::{ForwardDecay, g};
+    /// use fermentation::aggregate::BasicAggregator;
+    ///
+    /// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+    /// let aggregator: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(decay);
+    ///
+    /// aggregator.sum(Instant::now());
+    /// ```
+    #[must_use]
     pub fn sum(&self, timestamp: Instant) -> f64 {
-        self.sum / self.decay.normalizing_factor(timestamp)
+        self.saturate(self.sum.total() / self.decay.normalizing_factor(timestamp))
+    }
+
+    /// The decayed sum, or [None] when it is not finite.
+    /// Queries divide by the normalizing factor g(t − L), which is 0 for polynomial decay at the landmark
+    /// and overflows to infinity for exponential decay far from the landmark, producing an infinite or NaN sum.
+    #[must_use]
+    pub fn sum_finite(&self, timestamp: Instant) -> Option<f64> {
+        Some(self.sum(timestamp)).filter(|sum| sum.is_finite())
+    }
+
+    /// The average rate of change of the decayed sum per second between two timestamps.
+    /// Returns 0 when the timestamps are equal.
+    #[must_use]
+    pub fn rate_between(&self, start: Instant, end: Instant) -> f64 {
+        let elapsed = end.age(start);
+
+        if elapsed == 0.0 {
+            0.0
+        } else {
+            (self.sum(end) - self.sum(start)) / elapsed
+        }
     }
 
+    /// The sum of static weighted values, before normalization. Only comparable at a fixed landmark, see [Normalization].
+    #[must_use]
     pub fn static_sum(&self) -> f64 {
-        self.sum
+        self.sum.total()
+    }
+
+    /// The static or decayed sum, as chosen explicitly by the caller. The query time is ignored for static values.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use fermentation::{ForwardDecay, g};
+    /// use fermentation::aggregate::{Aggregator, BasicAggregator, Normalization};
+    ///
+    /// let landmark = Instant::now();
+    /// let now = landmark + Duration::from_secs(10);
+    /// let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)));
+    ///
+    /// aggregator.update((landmark + Duration::from_secs(5), 2.0));
+    ///
+    /// assert_eq!(aggregator.sum_as(now, Normalization::Static), 10.0);
+    /// assert_eq!(aggregator.sum_as(now, Normalization::Decayed), 1.0);
+    /// ```
+    #[must_use]
+    pub fn sum_as(&self, timestamp: Instant, normalization: Normalization) -> f64 {
+        match normalization {
+            Normalization::Static => self.static_sum(),
+            Normalization::Decayed => self.sum(timestamp),
+        }
     }
 
+    #[must_use]
     pub fn count(&self, timestamp: Instant) -> f64 {
-        self.count / self.decay.normalizing_factor(timestamp)
+        self.count.total() / self.decay.normalizing_factor(timestamp)
     }
 
+    /// The decayed count, or [None] when it is not finite. See [BasicAggregator::sum_finite].
+    #[must_use]
+    pub fn count_finite(&self, timestamp: Instant) -> Option<f64> {
+        Some(self.count(timestamp)).filter(|count| count.is_finite())
+    }
+
+    /// The decayed sum at the time of the context, saturated at the ceiling if one is set,
+    /// reusing the context's normalizing factor. See [QueryContext].
+    #[must_use = "the decayed sum should be used, or the error handled"]
+    pub fn sum_with(&self, context: &QueryContext<G>) -> Result<f64, Error>
+    where
+        G: PartialEq,
+    {
+        Ok(self.saturate(self.sum.total() / context.factor_for(&self.decay)?))
+    }
+
+    /// The decayed count at the time of the context, reusing the context's normalizing factor. See [QueryContext].
+    #[must_use = "the decayed count should be used, or the error handled"]
+    pub fn count_with(&self, context: &QueryContext<G>) -> Result<f64, Error>
+    where
+        G: PartialEq,
+    {
+        Ok(self.count.total() / context.factor_for(&self.decay)?)
+    }
+
+    /// The sum of static weights, before normalization. Only comparable at a fixed landmark, see [Normalization].
+    #[must_use]
     pub fn static_count(&self) -> f64 {
-        self.count
+        self.count.total()
     }
 
-    pub fn average(&self) -> f64 {
-        self.sum / self.count
+    /// The static or decayed count, as chosen explicitly by the caller. The query time is ignored for static values.
+    #[must_use]
+    pub fn count_as(&self, timestamp: Instant, normalization: Normalization) -> f64 {
+        match normalization {
+            Normalization::Static => self.static_count(),
+            Normalization::Decayed => self.count(timestamp),
+        }
     }
 
-    pub fn decay(&mut self) -> &ForwardDecay<G> {
-        &self.decay
+    /// The decayed sum, downcast to [f32] for export. The aggregation itself keeps [f64] precision.
+    #[must_use]
+    pub fn sum_f32(&self, timestamp: Instant) -> f32 {
+        self.sum(timestamp) as f32
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::ops::Add;
-    use std::time::{Duration, Instant};
-    use crate::g;
-    use super::*;
+    /// The decayed count, downcast to [f32] for export. The aggregation itself keeps [f64] precision.
+    #[must_use]
+    pub fn count_f32(&self, timestamp: Instant) -> f32 {
+        self.count(timestamp) as f32
+    }
 
-    #[test]
-    fn example() {
-        let landmark = Instant::now();
-        let now = landmark + Duration::from_secs(10);
-        let stream = vec![
-            (landmark.add(Duration::from_secs(5)), 4.0),
-            (landmark.add(Duration::from_secs(7)), 8.0),
-            (landmark.add(Duration::from_secs(3)), 3.0),
-            (landmark.add(Duration::from_secs(8)), 6.0),
-            (landmark.add(Duration::from_secs(4)), 4.0),
-        ];
+    #[must_use]
+    pub fn average(&self) -> f64 {
+        self.static_average()
+    }
 
-        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
-        let mut aggregator = BasicAggregator::new(fd);
+    /// The ratio of the static sum to the static count, which needs no query time.
+    /// Both the decayed sum and the decayed count are normalized by the same factor g(t − L),
+    /// so the factor cancels out of their ratio and the static average equals the decayed average at any time t.
+    #[must_use]
+    pub fn static_average(&self) -> f64 {
+        self.sum.total() / self.count.total()
+    }
 
-        for item in stream {
-            aggregator.update(item);
+    /// The decayed sum, count and average at the given time, computing the normalizing factor once.
+    /// Equivalent to calling [BasicAggregator::sum], [BasicAggregator::count] and [BasicAggregator::average] separately.
+    #[must_use]
+    pub fn snapshot(&self, timestamp: Instant) -> Snapshot {
+        let factor = self.decay.normalizing_factor(timestamp);
+
+        Snapshot {
+            sum: self.saturate(self.sum.total() / factor),
+            count: self.count.total() / factor,
+            average: self.static_average(),
         }
+    }
 
-        let epsilon = 0.01;
+    /// Consumes the aggregator, returning its final decayed sum, count and average at the given time,
+    /// i.e. to finalize a pipeline that builds and folds an aggregator by value.
+    #[must_use]
+    pub fn into_results(self, timestamp: Instant) -> Snapshot {
+        self.snapshot(timestamp)
+    }
 
-        assert_eq!(aggregator.sum(now), 9.67);
-        assert_eq!(aggregator.static_sum(), 967.0);
-        assert_eq!(aggregator.count(now), 1.63);
-        assert_eq!(aggregator.static_count(), 163.0);
-        assert!(aggregator.average() >= (5.93 - epsilon) && aggregator.average() <= (5.93 + epsilon));
+    /// The newest timestamp of any item aggregated since creation or the last reset.
+    /// Items may arrive out of order, so this is the maximum timestamp seen rather than that of the latest item.
+    #[must_use]
+    pub fn last_update(&self) -> Option<Instant> {
+        self.last_update
+    }
+
+    /// The value of the item with the newest timestamp aggregated since creation, or the value carried over by
+    /// [BasicAggregator::reset_preserving_last]. Among items with the same timestamp, the value of the latest one.
+    pub fn last_value(&self) -> Option<f64> {
+        self.last_value
+    }
+
+    /// Enables or disables strict ordering.
+    /// In strict mode, an item with a timestamp earlier than the newest timestamp seen so far is rejected
+    /// and counted as out of order, which helps catch ordering bugs in upstream producers.
+    pub fn set_strict_ordering(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Sets the maximum decayed sum returned by queries, or removes it when given [None].
+    /// Only the query output is clamped; the internal accumulator is unclamped,
+    /// so the sum falls below the ceiling only once the full, unclamped sum has decayed below it.
+    pub fn set_ceiling(&mut self, ceiling: Option<f64>) {
+        self.ceiling = ceiling;
+    }
+
+    /// Enables or disables anchoring the landmark to the first item.
+    /// When enabled, the first item aggregated since creation or the last reset moves the landmark to its own timestamp,
+    /// which avoids negative ages for the earliest data without having to know its timestamp upfront.
+    ///
+    /// Any landmark set before the first item, including one given to [Aggregator::reset] or `update_landmark`,
+    /// is replaced by the first item's timestamp. Once anchored, `update_landmark` moves the landmark as usual.
+    /// Note that the first item then has an age of 0, which gives it a weight of 0 under polynomial decay.
+    pub fn set_anchor_to_first_item(&mut self, anchor: bool) {
+        self.anchor = anchor;
+    }
+
+    /// The maximum decayed sum returned by queries, if any.
+    pub fn ceiling(&self) -> Option<f64> {
+        self.ceiling
+    }
+
+    /// The number of items rejected by strict ordering since creation or the last reset.
+    pub fn out_of_order(&self) -> u64 {
+        self.out_of_order
+    }
+
+    /// The policy for items that arrived before the landmark.
+    pub fn policy(&self) -> PreLandmarkPolicy {
+        self.policy
+    }
+
+    /// Sets the minimum static weight, including importance, for an item to be aggregated.
+    /// Items below it are skipped and counted as negligible, which saves work on streams with many items
+    /// that are very old relative to the landmark under steep decay.
+    ///
+    /// An item's decayed weight at time t is its static weight divided by g(t − L), so whenever g(t − L) ≥ 1,
+    /// each skipped item changes the decayed sum by less than the minimum times the magnitude of its value.
+    /// The error grows with the number of skipped items, so the minimum should be small relative to the expected totals.
+    pub fn set_min_contribution(&mut self, min_contribution: f64) {
+        self.min_contribution = min_contribution;
+    }
+
+    /// Sets a callback that is invoked whenever an item arrives with a timestamp earlier than the landmark
+    /// or earlier than the newest item aggregated so far, to surface clock skew and ordering bugs,
+    /// i.e. by logging a warning. The callback is purely diagnostic and does not change how the item is aggregated.
+    /// Items with a NaN value are skipped before the check.
+    pub fn set_warn_on_backwards_time(&mut self, callback: Option<fn(BackwardsTime)>) {
+        self.warn = callback;
+    }
+
+    /// The number of items skipped for contributing less than the minimum since creation or the last reset.
+    pub fn negligible(&self) -> u64 {
+        self.negligible
+    }
+
+    /// The number of items dropped by [PreLandmarkPolicy::Drop] since creation or the last reset.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// The number of items rejected by [PreLandmarkPolicy::Error] since creation or the last reset,
+    /// by updates that have no way to return the error, i.e. [Aggregator::update].
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    /// The number of items skipped because their value was NaN.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
     }
-}
\ No newline at end of file
+
+    /// Counts of the items seen since creation or the last reset, by how they were handled.
+    /// Values added with [BasicAggregator::update_raw] bypass the item handling, so they are not counted.
+    #[must_use]
+    pub fn stats(&self) -> Stats {
+        Stats {
+            processed: self.processed,
+            skipped_nan: self.skipped,
+            dropped_pre_landmark: self.dropped,
+            rejected_pre_landmark: self.rejected,
+            skipped_negligible: self.negligible,
+            out_of_order: self.out_of_order,
+        }
+    }
+
+    /// Renders the decayed sum, count and average at the given time in the Prometheus text exposition format,
+    /// as gauges named `{name}_sum`, `{name}_count` and `{name}_average`.
+    /// Labels are not supported yet; the name must already be a valid Prometheus metric name.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::Instant;
+    /// use fermentation::ForwardDecay;
+    /// use fermentation::aggregate::{BasicAggregator, Aggregator};
+    ///
+    /// let landmark = Instant::now();
+    /// let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+    ///
+    /// aggregator.update((landmark, 4.0));
+    ///
+    /// assert!(aggregator.to_prometheus("latency", landmark).contains("latency_sum 4\n"));
+    /// ```
+    #[must_use]
+    pub fn to_prometheus(&self, name: &str, timestamp: Instant) -> String {
+        let gauges = [
+            ("sum", "The decayed sum of the values.", self.sum(timestamp)),
+            ("count", "The decayed count of the values.", self.count(timestamp)),
+            ("average", "The decayed average of the values.", self.average()),
+        ];
+
+        gauges.into_iter()
+            .map(|(suffix, help, value)| {
+                format!("# HELP {name}_{suffix} {help}\n# TYPE {name}_{suffix} gauge\n{name}_{suffix} {}\n", prometheus_value(value))
+            })
+            .collect()
+    }
+
+    /// A diagnostic breakdown of the given items under this aggregator's decay model.
+    /// Returns the static weight and the normalized contribution to the decayed sum at the given time for each item.
+    pub fn explain<T>(&self, items: &[T], timestamp: Instant) -> Vec<(f64, f64)>
+    where
+        T: Item,
+    {
+        let factor = self.decay.normalizing_factor(timestamp);
+
+        items.iter()
+            .map(|item| {
+                let static_weight = self.decay.static_weight(item);
+
+                (static_weight, static_weight * item.value() / factor)
+            })
+            .collect()
+    }
+
+    pub fn decay(&mut self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+
+    /// Converts an empty aggregator to another summation strategy, keeping its options.
+    fn with_accumulators<B>(self) -> BasicAggregator<G, I, B>
+    where
+        B: Accumulator,
+    {
+        BasicAggregator {
+            decay: self.decay,
+            sum: B::default(),
+            count: B::default(),
+            processed: self.processed,
+            skipped: self.skipped,
+            last_update: self.last_update,
+            last_value: self.last_value,
+            strict: self.strict,
+            out_of_order: self.out_of_order,
+            policy: self.policy,
+            dropped: self.dropped,
+            rejected: self.rejected,
+            ceiling: self.ceiling,
+            anchor: self.anchor,
+            min_contribution: self.min_contribution,
+            negligible: self.negligible,
+            warn: self.warn,
+            pending: self.pending,
+            _phantom_data: Default::default()
+        }
+    }
+
+    /// Saturates a decayed sum at the ceiling, if one is set.
+    fn saturate(&self, sum: f64) -> f64 {
+        match self.ceiling {
+            Some(ceiling) if sum > ceiling => ceiling,
+            _ => sum,
+        }
+    }
+
+    fn add<T>(&mut self, item: T)
+    where
+        T: WeightedItem,
+    {
+        if let Err(Error::PreLandmark(_)) = self.try_add(item) {
+            self.rejected += 1;
+        }
+    }
+
+    fn try_add<T>(&mut self, item: T) -> Result<(), Error>
+    where
+        T: WeightedItem,
+    {
+        let timestamp = item.timestamp();
+        let value = item.value();
+
+        if self.admit(timestamp, value) {
+            if let Some(static_weight) = self.static_weight(&item)? {
+                self.contribute(timestamp, static_weight * item.importance(), value);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn static_weight<T>(&mut self, item: &T) -> Result<Option<f64>, Error>
+    where
+        T: Item,
+    {
+        let age = item.age(self.decay.landmark());
+
+        if age >= 0.0 {
+            return Ok(Some(self.decay.static_weight(item)));
+        }
+
+        match self.policy {
+            PreLandmarkPolicy::Drop => {
+                self.dropped += 1;
+                Ok(None)
+            }
+            PreLandmarkPolicy::Clamp => Ok(Some(self.decay.static_weight(self.decay.landmark()))),
+            PreLandmarkPolicy::Error => Err(Error::PreLandmark(age)),
+        }
+    }
+
+    fn admit(&mut self, timestamp: Instant, value: f64) -> bool {
+        if value.is_nan() {
+            self.skipped += 1;
+            return false;
+        }
+
+        if let Some(warn) = self.warn {
+            let landmark = self.decay.landmark();
+
+            if timestamp < landmark {
+                warn(BackwardsTime::BeforeLandmark { timestamp, landmark });
+            }
+
+            if let Some(last_update) = self.last_update.filter(|last| timestamp < *last) {
+                warn(BackwardsTime::BeforeLastUpdate { timestamp, last_update });
+            }
+        }
+
+        if self.strict && self.last_update.is_some_and(|last| timestamp < last) {
+            self.out_of_order += 1;
+            return false;
+        }
+
+        if self.anchor && self.last_update.is_none() && self.count.total() == 0.0 {
+            self.decay.set_landmark(timestamp);
+        }
+
+        true
+    }
+
+    /// Accumulates an admitted item, unless its static weight is below the minimum contribution.
+    fn contribute(&mut self, timestamp: Instant, static_weight: f64, value: f64) {
+        if static_weight < self.min_contribution {
+            self.negligible += 1;
+        } else {
+            self.accumulate(timestamp, static_weight, value);
+        }
+    }
+
+    fn accumulate(&mut self, timestamp: Instant, static_weight: f64, value: f64) {
+        self.sum.add(static_weight * value);
+        self.count.add(static_weight);
+        self.processed += 1;
+
+        if self.last_update.is_none_or(|last| timestamp >= last) {
+            self.last_update = Some(timestamp);
+            self.last_value = Some(value);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<G, I, A> BasicAggregator<G, I, A>
+where
+    G: Function + std::fmt::Debug,
+    I: Item,
+    A: Accumulator,
+{
+    /// A human-readable JSON snapshot of the aggregator's state for debugging.
+    /// The landmark is given as its age in seconds at the reference time, since an [Instant] has no absolute representation,
+    /// and the decayed sum and count are normalized at the reference time.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use fermentation::{ForwardDecay, g};
+    /// use fermentation::aggregate::{BasicAggregator, Aggregator};
+    ///
+    /// let landmark = Instant::now();
+    /// let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+    ///
+    /// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+    ///
+    /// let json = aggregator.to_json(landmark + Duration::from_secs(10));
+    ///
+    /// assert_eq!(json, r#"{"landmark_age":10.0,"sum":1.0,"count":0.25,"static_sum":100.0,"static_count":25.0,"decay":"Polynomial(2)"}"#);
+    /// ```
+    pub fn to_json(&self, reference: Instant) -> String {
+        let snapshot = JsonSnapshot {
+            landmark_age: reference.age(self.decay.landmark()),
+            sum: self.sum(reference),
+            count: self.count(reference),
+            static_sum: self.static_sum(),
+            static_count: self.static_count(),
+            decay: format!("{:?}", self.decay.g()),
+        };
+
+        serde_json::to_string(&snapshot).expect("snapshot is always serializable")
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct JsonSnapshot {
+    landmark_age: f64,
+    sum: f64,
+    count: f64,
+    static_sum: f64,
+    static_count: f64,
+    decay: String,
+}
+
+/// Formats a sample value, spelling out the special values the way the Prometheus text format expects.
+fn prometheus_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+impl<G, I, A> Checkpoint<G> for BasicAggregator<G, I, A>
+where
+    G: Function,
+    I: Item,
+    A: Accumulator,
+{
+    /// Captures the static sum and count.
+    fn checkpoint(&self, reference: Instant) -> CheckpointData {
+        CheckpointData::new(self.decay.landmark(), reference, vec![self.sum.total(), self.count.total()])
+    }
+
+    fn restore(data: &CheckpointData, mut decay: ForwardDecay<G>, reference: Instant) -> Result<Self, Error> {
+        let [sum, count] = data.values()?;
+
+        decay.set_landmark(data.landmark(reference)?);
+
+        Ok(Self::from_parts(decay, sum, count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Add;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use crate::aggregate::{Kahan, QuantileAggregator};
+    use crate::testing::{self, Query, Rescalable};
+    use super::*;
+
+    impl Rescalable for BasicAggregator<Exponential, (Instant, f64)> {
+        fn rescale(&mut self, landmark: Instant) {
+            self.update_landmark(landmark);
+        }
+    }
+
+    impl Query for BasicAggregator<Exponential, (Instant, f64)> {
+        fn ingest(&mut self, timestamp: Instant, value: f64) {
+            self.update((timestamp, value));
+        }
+
+        fn query(&self, timestamp: Instant) -> Vec<f64> {
+            vec![self.sum(timestamp), self.count(timestamp), self.average()]
+        }
+    }
+
+    #[test]
+    fn rescale_invariant() {
+        testing::assert_rescale_invariant(BasicAggregator::new);
+    }
+
+    #[test]
+    fn example() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let stream = vec![
+            (landmark.add(Duration::from_secs(5)), 4.0),
+            (landmark.add(Duration::from_secs(7)), 8.0),
+            (landmark.add(Duration::from_secs(3)), 3.0),
+            (landmark.add(Duration::from_secs(8)), 6.0),
+            (landmark.add(Duration::from_secs(4)), 4.0),
+        ];
+
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        for item in stream {
+            aggregator.update(item);
+        }
+
+        let epsilon = 0.01;
+
+        assert_eq!(aggregator.sum(now), 9.67);
+        assert_eq!(aggregator.static_sum(), 967.0);
+        assert_eq!(aggregator.count(now), 1.63);
+        assert_eq!(aggregator.static_count(), 163.0);
+        assert!(aggregator.average() >= (5.93 - epsilon) && aggregator.average() <= (5.93 + epsilon));
+    }
+
+    #[test]
+    fn subsecond() {
+        let epsilon = 1e-9;
+
+        // Polynomial weights only depend on the ratio of ages, so rescaling the example to sub-second units must not change the results.
+        for unit in [Duration::from_millis(1), Duration::from_micros(1), Duration::from_nanos(100)] {
+            let landmark = Instant::now();
+            let now = landmark + unit * 10;
+            let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+            for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+                aggregator.update((landmark + unit * offset, value));
+            }
+
+            assert!((aggregator.sum(now) - 9.67).abs() < epsilon);
+            assert!((aggregator.count(now) - 1.63).abs() < epsilon);
+        }
+
+        // An item one half-life before the query time has a weight of 0.5, at millisecond and microsecond scales.
+        for half_life in [Duration::from_millis(20), Duration::from_micros(20)] {
+            let landmark = Instant::now();
+            let now = landmark + half_life * 3;
+            let decay = ForwardDecay::new(landmark, g::Exponential::rate(0.5, half_life));
+            let mut aggregator = BasicAggregator::new(decay);
+
+            aggregator.update((now - half_life, 1.0));
+            aggregator.update((now - half_life / 2, 1.0));
+            aggregator.update((now, 1.0));
+
+            assert!((aggregator.sum(now) - (0.5 + 0.5_f64.sqrt() + 1.0)).abs() < epsilon);
+        }
+    }
+
+    #[test]
+    fn builder() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::builder(ForwardDecay::new(landmark, g::Polynomial::new(1)))
+            .policy(PreLandmarkPolicy::Error)
+            .strict_ordering(true)
+            .build();
+
+        assert_eq!(aggregator.policy(), PreLandmarkPolicy::Error);
+        assert_eq!(aggregator.try_update((landmark - Duration::from_secs(1), 1.0)), Err(Error::PreLandmark(-1.0)));
+
+        aggregator.update((landmark + Duration::from_secs(5), 2.0));
+        aggregator.update((landmark + Duration::from_secs(4), 3.0));
+
+        assert_eq!(aggregator.out_of_order(), 1);
+        assert_eq!(aggregator.sum(now), 1.0);
+    }
+
+    #[test]
+    fn ceiling() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let mut aggregator = BasicAggregator::builder(ForwardDecay::new(landmark, ()))
+            .ceiling(50.0)
+            .build();
+
+        for offset in 1..=100 {
+            aggregator.update((landmark + Duration::from_secs(offset), 1.0));
+        }
+
+        assert_eq!(aggregator.ceiling(), Some(50.0));
+        assert_eq!(aggregator.sum(now), 50.0);
+        assert_eq!(aggregator.static_sum(), 100.0);
+        assert_eq!(aggregator.average(), 1.0);
+
+        aggregator.set_ceiling(None);
+
+        assert_eq!(aggregator.sum(now), 100.0);
+    }
+
+    #[test]
+    fn static_state() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let state = aggregator.static_state();
+        let factor = aggregator.decay().normalizing_factor(now);
+
+        assert_eq!(state.landmark, landmark);
+        assert_eq!(state.sum / factor, aggregator.sum(now));
+        assert_eq!(state.count / factor, aggregator.count(now));
+    }
+
+    #[test]
+    fn sum_finite() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut polynomial = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        polynomial.update((now, 1.0));
+
+        assert_eq!(polynomial.sum_finite(now), Some(1.0));
+        assert_eq!(polynomial.sum_finite(landmark), None);
+        assert_eq!(polynomial.count_finite(now), Some(1.0));
+        assert_eq!(polynomial.count_finite(landmark), None);
+        assert_eq!(polynomial.query_finite(now), Some(1.0));
+        assert_eq!(polynomial.query_finite(landmark), None);
+
+        // Static weights overflow to infinity, so the normalized sum is infinity divided by infinity.
+        let far = landmark + Duration::from_secs(1000);
+        let mut exponential = BasicAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(1.0)));
+
+        exponential.update((far, 1.0));
+
+        assert!(exponential.sum(far).is_nan());
+        assert_eq!(exponential.sum_finite(far), None);
+
+        exponential.set_ceiling(Some(1.0));
+
+        assert_eq!(exponential.sum_finite(far), None);
+    }
+
+    #[test]
+    fn merge() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut left = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+        let mut right = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+        let moved = BasicAggregator::new(ForwardDecay::new(now, g::Polynomial::new(2)));
+
+        left.update((landmark + Duration::from_secs(5), 4.0));
+        right.update((landmark + Duration::from_secs(7), 8.0));
+        right.update((landmark + Duration::from_secs(3), f64::NAN));
+
+        assert_eq!(left.merge(&moved), Err(Error::LandmarkMismatch));
+        assert_eq!(left.merge(&right), Ok(()));
+        assert_eq!(left.sum(now), 4.0 * 0.25 + 8.0 * 0.49);
+        assert_eq!(left.skipped(), 1);
+        assert_eq!(left.last_update(), Some(landmark + Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn merge_decay_mismatch() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut left = BasicAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.1)));
+        let right = BasicAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.2)));
+        let scaled = BasicAggregator::new(ForwardDecay::with_time_scale(landmark, g::Exponential::new(0.1), 60.0));
+
+        left.update((landmark + Duration::from_secs(5), 4.0));
+
+        assert_eq!(left.merge(&right), Err(Error::DecayMismatch));
+        assert_eq!(left.merge(&scaled), Err(Error::DecayMismatch));
+        assert_eq!(left.sum(now), 4.0 * g::Exponential::new(0.1).invoke(-5.0));
+    }
+
+    #[test]
+    fn merge_into() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let stream = [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)];
+        let shards: Vec<BasicAggregator<_, (Instant, f64)>> = stream.iter()
+            .map(|(offset, value)| {
+                let mut shard = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+                shard.update((landmark + Duration::from_secs(*offset), *value));
+                shard
+            })
+            .collect();
+
+        let merged = shards.into_iter().reduce(BasicAggregator::merge_into).unwrap();
+
+        assert_eq!(merged.sum(now), 9.67);
+        assert_eq!(merged.count(now), 1.63);
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_into_mismatch() {
+        let landmark = Instant::now();
+        let left: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+        let right = BasicAggregator::new(ForwardDecay::new(landmark + Duration::from_secs(1), ()));
+
+        left.merge_into(right);
+    }
+
+    #[test]
+    fn importance() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let timestamp = landmark + Duration::from_secs(5);
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+        let mut control = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        aggregator.update((timestamp, 4.0, 1.0));
+        control.update((timestamp, 4.0));
+
+        assert_eq!(aggregator.sum(now), control.sum(now));
+
+        aggregator.update((timestamp, 4.0, 3.0));
+
+        assert_eq!(aggregator.sum(now), 4.0 * control.sum(now));
+        assert_eq!(aggregator.count(now), 4.0 * control.count(now));
+        assert_eq!(aggregator.average(), 4.0);
+    }
+
+    #[test]
+    fn static_average() {
+        let landmark = Instant::now();
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        assert_eq!(aggregator.static_average(), aggregator.average());
+
+        for offset in [10, 20, 100] {
+            let now = landmark + Duration::from_secs(offset);
+
+            assert!((aggregator.static_average() - aggregator.sum(now) / aggregator.count(now)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn anchor_to_first_item() {
+        let landmark = Instant::now();
+        let first = landmark + Duration::from_secs(5);
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::builder(ForwardDecay::new(landmark, g::Exponential::new(0.2)))
+            .anchor_to_first_item(true)
+            .build();
+
+        aggregator.update((landmark + Duration::from_secs(3), f64::NAN));
+
+        assert_eq!(aggregator.decay().landmark(), landmark);
+
+        aggregator.update((first, 4.0));
+        aggregator.update((landmark + Duration::from_secs(7), 8.0));
+
+        assert_eq!(aggregator.decay().landmark(), first);
+        assert_eq!(aggregator.static_count(), 1.0 + 0.4_f64.exp());
+
+        let mut control = BasicAggregator::new(ForwardDecay::new(first, g::Exponential::new(0.2)));
+
+        control.update((first, 4.0));
+        control.update((landmark + Duration::from_secs(7), 8.0));
+
+        assert_eq!(aggregator.sum(now), control.sum(now));
+
+        aggregator.reset(landmark);
+        aggregator.update_batch_same_time(now, &[1.0, 2.0]);
+
+        assert_eq!(aggregator.decay().landmark(), now);
+        assert_eq!(aggregator.sum(now), 3.0);
+    }
+
+    #[test]
+    fn drain_channel() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        sender.send((landmark + Duration::from_secs(5), 4.0)).unwrap();
+        sender.send((landmark + Duration::from_secs(7), 8.0)).unwrap();
+
+        assert_eq!(aggregator.drain_channel(&receiver), 2);
+        assert_eq!(aggregator.drain_channel(&receiver), 0);
+
+        let producer = std::thread::spawn(move || {
+            for (offset, value) in [(3, 3.0), (8, 6.0), (4, 4.0)] {
+                sender.send((landmark + Duration::from_secs(offset), value)).unwrap();
+            }
+        });
+
+        assert_eq!(aggregator.drain_channel_until_closed(&receiver), 3);
+        assert_eq!(aggregator.sum(now), 9.67);
+
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn extend() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let stream = vec![
+            (landmark + Duration::from_secs(5), 4.0),
+            (landmark + Duration::from_secs(7), 8.0),
+            (landmark + Duration::from_secs(3), 3.0),
+        ];
+
+        let mut extended = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+        let mut looped = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        extended.extend(stream.clone());
+
+        for item in stream {
+            looped.update(item);
+        }
+
+        assert_eq!(extended.sum(now), looped.sum(now));
+        assert_eq!(extended.count(now), looped.count(now));
+        assert_eq!(extended.last_update(), looped.last_update());
+    }
+
+    #[test]
+    fn compensated_accumulator() {
+        let landmark = Instant::now();
+        let mut naive = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+        let mut kahan = BasicAggregator::builder(ForwardDecay::new(landmark, ()))
+            .strict_ordering(true)
+            .accumulator::<Kahan>()
+            .build();
+
+        naive.update((landmark, 1.0));
+        kahan.update((landmark, 1.0));
+
+        for _ in 0..1_000_000 {
+            naive.update((landmark, 1e-16));
+            kahan.update((landmark, 1e-16));
+        }
+
+        let expected = 1.0 + 1e-10;
+
+        assert!((kahan.sum(landmark) - expected).abs() < (naive.sum(landmark) - expected).abs());
+        assert!((kahan.sum(landmark) - expected).abs() < 1e-15);
+        assert_eq!(kahan.count(landmark), 1_000_001.0);
+    }
+
+    #[test]
+    fn to_prometheus() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let text = aggregator.to_prometheus("request_latency", now);
+        let samples: HashMap<&str, f64> = text.lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (name, value) = line.split_once(' ').unwrap();
+
+                (name, value.parse().unwrap())
+            })
+            .collect();
+
+        assert_eq!(text.lines().filter(|line| line.starts_with("# TYPE")).count(), 3);
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples["request_latency_sum"], aggregator.sum(now));
+        assert_eq!(samples["request_latency_count"], aggregator.count(now));
+        assert_eq!(samples["request_latency_average"], aggregator.average());
+
+        let empty: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+
+        assert!(empty.to_prometheus("empty", now).contains("empty_average NaN\n"));
+    }
+
+    #[test]
+    fn maybe_reset() {
+        let landmark = Instant::now();
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+        let mut resets = Vec::new();
+
+        for offset in 1..=10 {
+            let timestamp = landmark + Duration::from_secs(offset);
+
+            aggregator.update((timestamp, 1.0));
+
+            if aggregator.maybe_reset(timestamp, |aggregator, now| aggregator.count(now) > 3.0) {
+                resets.push(offset);
+            }
+        }
+
+        assert_eq!(resets, vec![4, 8]);
+        assert_eq!(aggregator.decay().landmark(), landmark + Duration::from_secs(8));
+        assert_eq!(aggregator.count(landmark + Duration::from_secs(10)), 2.0);
+    }
+
+    #[test]
+    fn min_contribution() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(110);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut filtered = BasicAggregator::builder(decay)
+            .min_contribution(1e-4)
+            .build();
+        let mut unfiltered = BasicAggregator::new(decay);
+
+        // Items within 10ms of the landmark have static weights below 1e-4.
+        let stream = (1..=1000).map(|offset| (landmark + Duration::from_millis(offset), 1.0))
+            .chain((5..105).map(|offset| (landmark + Duration::from_secs(offset), 1.0)));
+
+        for item in stream {
+            filtered.update(item);
+            unfiltered.update(item);
+        }
+
+        assert_eq!(filtered.negligible(), 9);
+        assert_eq!(unfiltered.negligible(), 0);
+        assert!((filtered.sum(now) - unfiltered.sum(now)).abs() < 9.0 * 1e-4 / decay.normalizing_factor(now));
+
+        filtered.reset(landmark);
+
+        assert_eq!(filtered.negligible(), 0);
+
+        // Batches share a static weight, which is checked against the minimum like a single item's.
+        filtered.update_batch_same_time(landmark + Duration::from_millis(5), &[1.0, 2.0, 3.0]);
+        filtered.update_batch_same_time(landmark + Duration::from_secs(5), &[1.0, 2.0]);
+
+        assert_eq!(filtered.negligible(), 3);
+        assert_eq!(filtered.stats().skipped_negligible, 3);
+        assert_eq!(filtered.stats().processed, 2);
+        assert_eq!(filtered.static_count(), 50.0);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn to_json() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.5)));
+
+        aggregator.update((landmark + Duration::from_secs(4), 2.0));
+
+        let json: serde_json::Value = serde_json::from_str(&aggregator.to_json(now)).unwrap();
+
+        let field = |name: &str| json[name].as_f64().unwrap();
+        let epsilon = 1e-12;
+
+        assert_eq!(field("landmark_age"), 10.0);
+        assert!((field("sum") - aggregator.sum(now)).abs() < epsilon);
+        assert!((field("count") - aggregator.count(now)).abs() < epsilon);
+        assert!((field("static_sum") - 2.0 * 2.0_f64.exp()).abs() < epsilon);
+        assert!((field("static_count") - 2.0_f64.exp()).abs() < epsilon);
+        assert_eq!(json["decay"], "Exponential(0.5)");
+    }
+
+    #[test]
+    fn skips_nan() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        aggregator.update((landmark + Duration::from_secs(5), 4.0));
+        aggregator.update((landmark + Duration::from_secs(7), f64::NAN));
+
+        assert_eq!(aggregator.sum(now), 1.0);
+        assert_eq!(aggregator.count(now), 0.25);
+        assert_eq!(aggregator.skipped(), 1);
+
+        aggregator.reset(landmark);
+
+        assert_eq!(aggregator.skipped(), 0);
+    }
+
+    #[test]
+    fn update_and_query() {
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        aggregator.update((landmark + Duration::from_secs(5), 4.0));
+
+        let item = (landmark + Duration::from_secs(10), 8.0);
+        let sum = aggregator.update_and_query(item);
+
+        assert_eq!(sum, aggregator.sum(item.0));
+        assert_eq!(sum, 9.0);
+    }
+
+    #[test]
+    fn update_buckets() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let buckets = HashMap::from([
+            (landmark + Duration::from_secs(5), 4.0),
+            (landmark + Duration::from_secs(7), 8.0),
+            (landmark + Duration::from_secs(3), 3.0),
+            (landmark + Duration::from_secs(8), 6.0),
+            (landmark + Duration::from_secs(4), 4.0),
+        ]);
+
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(fd);
+        let mut expected = BasicAggregator::new(fd);
+
+        aggregator.update_buckets(&buckets);
+
+        for item in buckets {
+            expected.update(item);
+        }
+
+        assert_eq!(aggregator.sum(now), expected.sum(now));
+        assert_eq!(aggregator.count(now), expected.count(now));
+    }
+
+    #[test]
+    fn last_update() {
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        assert_eq!(aggregator.last_update(), None);
+
+        for i in [5, 7, 3, 8, 4] {
+            aggregator.update((landmark + Duration::from_secs(i), 1.0));
+        }
+
+        assert_eq!(aggregator.last_update(), Some(landmark + Duration::from_secs(8)));
+
+        aggregator.reset(landmark);
+
+        assert_eq!(aggregator.last_update(), None);
+    }
+
+    #[test]
+    fn observe() {
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, ());
+        let mut aggregator: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(fd);
+        let mut expected = BasicAggregator::new(fd);
+
+        for value in [4.0, 8.0, 3.0] {
+            aggregator.observe(value);
+            expected.update((Instant::now(), value));
+        }
+
+        assert_eq!(aggregator.sum(landmark), expected.sum(landmark));
+        assert_eq!(aggregator.count(landmark), expected.count(landmark));
+        assert!(aggregator.last_update() >= Some(landmark));
+    }
+
+    #[test]
+    fn explain() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let stream = vec![
+            (landmark + Duration::from_secs(5), 4.0),
+            (landmark + Duration::from_secs(7), 8.0),
+            (landmark + Duration::from_secs(3), 3.0),
+            (landmark + Duration::from_secs(8), 6.0),
+            (landmark + Duration::from_secs(4), 4.0),
+        ];
+
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        for item in stream.iter() {
+            aggregator.update(*item);
+        }
+
+        let explanation = aggregator.explain(&stream, now);
+        let total: f64 = explanation.iter().map(|(_, contribution)| contribution).sum();
+
+        assert_eq!(explanation[0], (25.0, 1.0));
+        assert!((total - aggregator.sum(now)).abs() < 0.0001);
+    }
+
+    #[test]
+    fn update_results() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        #[derive(Debug, PartialEq)]
+        enum Invalid {
+            Parse(&'static str),
+            Aggregate(Error),
+        }
+
+        impl From<Error> for Invalid {
+            fn from(error: Error) -> Self {
+                Invalid::Aggregate(error)
+            }
+        }
+
+        let stream = vec![
+            Ok((landmark + Duration::from_secs(5), 4.0)),
+            Err(Invalid::Parse("invalid")),
+            Ok((landmark + Duration::from_secs(7), 8.0)),
+            Err(Invalid::Parse("missing")),
+            Ok((landmark - Duration::from_secs(2), 1.0)),
+        ];
+
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::with_policy(fd, PreLandmarkPolicy::Error);
+        let errors = aggregator.update_results(stream);
+
+        assert_eq!(errors, vec![
+            Invalid::Parse("invalid"),
+            Invalid::Parse("missing"),
+            Invalid::Aggregate(Error::PreLandmark(-2.0)),
+        ]);
+        assert_eq!(aggregator.rejected(), 0);
+        assert_eq!(aggregator.static_sum(), 25.0 * 4.0 + 49.0 * 8.0);
+        assert_eq!(aggregator.count(now), 0.74);
+    }
+
+    #[test]
+    fn strict_ordering() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        aggregator.set_strict_ordering(true);
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (7, 1.0), (8, 6.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        assert_eq!(aggregator.out_of_order(), 1);
+        assert_eq!(aggregator.count(now), 0.25 + 0.49 + 0.49 + 0.64);
+    }
+
+    #[test]
+    fn rate_between() {
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, ());
+        let mut aggregator = BasicAggregator::new(fd);
+
+        for offset in 1..=10 {
+            aggregator.update((landmark + Duration::from_secs(offset), 2.0));
+        }
+
+        let start = landmark + Duration::from_secs(1);
+        let end = landmark + Duration::from_secs(10);
+
+        assert_eq!(aggregator.rate_between(start, start), 0.0);
+        assert_eq!(aggregator.rate_between(start, end), 0.0);
+
+        let fd = ForwardDecay::new(landmark, g::Exponential::new(0.1));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        for offset in 1..=10 {
+            aggregator.update((landmark + Duration::from_secs(offset), 2.0));
+        }
+
+        let rate = aggregator.rate_between(end, end + Duration::from_secs(1));
+
+        assert!(rate < 0.0);
+        assert!(rate > -aggregator.sum(end));
+    }
+
+    #[test]
+    fn update_batch_same_time() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let timestamp = landmark + Duration::from_secs(5);
+        let values = [4.0, 8.0, 3.0, 6.0, 4.0];
+
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(fd);
+        let mut expected = BasicAggregator::new(fd);
+
+        aggregator.update_batch_same_time(timestamp, &values);
+
+        for value in values {
+            expected.update((timestamp, value));
+        }
+
+        assert_eq!(aggregator.sum(now), expected.sum(now));
+        assert_eq!(aggregator.count(now), expected.count(now));
+        assert_eq!(aggregator.last_update(), Some(timestamp));
+    }
+
+    #[test]
+    fn downcast() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Exponential::new(0.3));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        for (offset, value) in [(5, 4.1), (7, 8.3), (3, 3.7)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let sum = aggregator.sum(now);
+        let count = aggregator.count(now);
+
+        assert!((aggregator.sum_f32(now) as f64 - sum).abs() <= sum.abs() * f32::EPSILON as f64);
+        assert!((aggregator.count_f32(now) as f64 - count).abs() <= count.abs() * f32::EPSILON as f64);
+    }
+
+    #[test]
+    fn pre_landmark_policy() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let early = (landmark - Duration::from_secs(2), 5.0);
+        let item = (landmark + Duration::from_secs(5), 4.0);
+
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut drop = BasicAggregator::new(fd);
+        let mut clamp = BasicAggregator::with_policy(fd, PreLandmarkPolicy::Clamp);
+        let mut error = BasicAggregator::with_policy(fd, PreLandmarkPolicy::Error);
+
+        assert_eq!(drop.policy(), PreLandmarkPolicy::Drop);
+
+        for aggregator in [&mut drop, &mut clamp] {
+            aggregator.update(item);
+            aggregator.update(early);
+        }
+
+        assert_eq!(drop.count(now), 0.25);
+        assert_eq!(drop.dropped(), 1);
+
+        // Clamping to the landmark gives a weight of g(0), which is 0 for polynomial decay.
+        assert_eq!(clamp.count(now), 0.25);
+        assert_eq!(clamp.dropped(), 0);
+
+        let mut exponential = BasicAggregator::with_policy(ForwardDecay::new(landmark, g::Exponential::new(1.0)), PreLandmarkPolicy::Clamp);
+
+        exponential.update(early);
+
+        assert_eq!(exponential.static_count(), 1.0);
+        assert_eq!(exponential.static_sum(), 5.0);
+
+        assert_eq!(error.try_update(item), Ok(()));
+        assert_eq!(error.try_update(early), Err(Error::PreLandmark(-2.0)));
+        assert_eq!(error.count(now), 0.25);
+    }
+
+    #[test]
+    fn pre_landmark_error_rejects() {
+        let landmark = Instant::now();
+        let early = landmark - Duration::from_secs(2);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::with_policy(fd, PreLandmarkPolicy::Error);
+
+        // Updates that cannot return the error skip the item instead of panicking.
+        aggregator.update((early, 5.0));
+        aggregator.extend([(early, 1.0), (landmark + Duration::from_secs(5), 4.0)]);
+        aggregator.update_batch_same_time(early, &[1.0, 2.0]);
+        aggregator.observe_many(&[early], &[3.0]);
+
+        assert_eq!(aggregator.rejected(), 5);
+        assert_eq!(aggregator.stats().rejected_pre_landmark, 5);
+        assert_eq!(aggregator.static_sum(), 100.0);
+
+        aggregator.reset(landmark);
+
+        assert_eq!(aggregator.rejected(), 0);
+    }
+
+    #[test]
+    fn from_state() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let state = aggregator.static_state();
+        let other = ForwardDecay::new(landmark + Duration::from_secs(1), g::Polynomial::new(2));
+        let rebuilt: BasicAggregator<_, (Instant, f64)> = BasicAggregator::from_state(other, state);
+
+        assert_eq!(state, AggregatorState { sum: 519.0, count: 83.0, landmark });
+        assert_eq!(rebuilt.static_state(), state);
+        assert_eq!(rebuilt.sum(now), aggregator.sum(now));
+
+        let compensated: BasicAggregator<_, (Instant, f64), Kahan> = BasicAggregator::from_state(other, state);
+
+        assert_eq!(compensated.static_state(), state);
+        assert_eq!(rebuilt.count(now), aggregator.count(now));
+    }
+
+    #[test]
+    fn update_raw() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(fd);
+        let mut expected = BasicAggregator::new(fd);
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0)] {
+            let item = (landmark + Duration::from_secs(offset), value);
+
+            aggregator.update_raw(fd.static_weight(item), value);
+            expected.update(item);
+        }
+
+        assert_eq!(aggregator.sum(now), expected.sum(now));
+        assert_eq!(aggregator.count(now), expected.count(now));
+        assert_eq!(aggregator.last_update(), None);
+    }
+
+    #[test]
+    fn exact_count() {
+        let landmark = Instant::now();
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, ()));
+
+        assert_eq!(aggregator.exact_count(), Some(0));
+
+        for offset in 0..1000 {
+            aggregator.update((landmark + Duration::from_millis(offset), 0.1));
+        }
+
+        assert_eq!(aggregator.exact_count(), Some(1000));
+
+        aggregator.update_raw(0.5, 1.0);
+
+        assert_eq!(aggregator.exact_count(), None);
+    }
+
+    #[test]
+    fn warn_on_backwards_time() {
+        static WARNINGS: Mutex<Vec<BackwardsTime>> = Mutex::new(Vec::new());
+
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::builder(fd)
+            .policy(PreLandmarkPolicy::Clamp)
+            .warn_on_backwards_time(|warning| WARNINGS.lock().unwrap().push(warning))
+            .build();
+        let mut control = BasicAggregator::with_policy(fd, PreLandmarkPolicy::Clamp);
+        let stream = [
+            (landmark + Duration::from_secs(5), 4.0),
+            (landmark + Duration::from_secs(7), 8.0),
+            (landmark + Duration::from_secs(3), 3.0),
+            (landmark - Duration::from_secs(1), 6.0),
+        ];
+
+        for item in stream {
+            aggregator.update(item);
+            control.update(item);
+        }
+
+        let last_update = landmark + Duration::from_secs(7);
+
+        assert_eq!(*WARNINGS.lock().unwrap(), vec![
+            BackwardsTime::BeforeLastUpdate { timestamp: landmark + Duration::from_secs(3), last_update },
+            BackwardsTime::BeforeLandmark { timestamp: landmark - Duration::from_secs(1), landmark },
+            BackwardsTime::BeforeLastUpdate { timestamp: landmark - Duration::from_secs(1), last_update },
+        ]);
+        assert_eq!(aggregator.static_sum(), control.static_sum());
+    }
+
+    #[test]
+    fn stats() {
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::builder(fd)
+            .strict_ordering(true)
+            .min_contribution(1.0)
+            .build();
+
+        aggregator.update((landmark - Duration::from_secs(1), 3.0, 1.0));
+        aggregator.update((landmark + Duration::from_secs(5), 4.0, 1.0));
+        aggregator.update((landmark + Duration::from_secs(7), 8.0, 1.0));
+        aggregator.update((landmark + Duration::from_secs(8), f64::NAN, 1.0));
+        aggregator.update((landmark + Duration::from_secs(6), 6.0, 1.0));
+        aggregator.update((landmark + Duration::from_millis(7500), 1.0, 0.01));
+        aggregator.update((landmark + Duration::from_secs(9), 2.0, 1.0));
+
+        assert_eq!(aggregator.stats(), Stats {
+            processed: 3,
+            skipped_nan: 1,
+            dropped_pre_landmark: 1,
+            rejected_pre_landmark: 0,
+            skipped_negligible: 1,
+            out_of_order: 1,
+        });
+
+        aggregator.reset(landmark);
+
+        assert_eq!(aggregator.stats(), Stats::default());
+    }
+
+    #[test]
+    fn checkpoint() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let data = aggregator.checkpoint(now);
+        let reference = now + Duration::from_secs(3600);
+        let restored: BasicAggregator<_, (Instant, f64)> = BasicAggregator::restore(&data, ForwardDecay::new(reference, g::Polynomial::new(2)), reference).unwrap();
+        let epsilon = 1e-9;
+
+        assert_eq!(data.landmark_age, 10.0);
+        assert_eq!(restored.static_state().landmark, landmark + Duration::from_secs(3600));
+        assert!((restored.sum(reference) - aggregator.sum(now)).abs() < epsilon);
+        assert!((restored.count(reference) - aggregator.count(now)).abs() < epsilon);
+
+        let invalid = CheckpointData { landmark_age: 0.0, values: vec![1.0] };
+
+        assert!(matches!(BasicAggregator::<_, (Instant, f64)>::restore(&invalid, ForwardDecay::new(now, ()), now), Err(Error::InvalidCheckpoint)));
+    }
+
+    #[test]
+    fn snapshot() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::builder(ForwardDecay::new(landmark, g::Polynomial::new(2)))
+            .ceiling(5.0)
+            .build();
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let snapshot = aggregator.snapshot(now);
+
+        assert_eq!(snapshot, Snapshot {
+            sum: aggregator.sum(now),
+            count: aggregator.count(now),
+            average: aggregator.average(),
+        });
+        assert_eq!(snapshot.sum, 5.0);
+    }
+
+    #[test]
+    fn observe_many() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let timestamps = [5, 7, 3, 8, 4].map(|offset| landmark + Duration::from_secs(offset));
+        let values = [4.0, 8.0, 3.0, 6.0, 4.0];
+        let mut columnar = BasicAggregator::<_, (Instant, f64)>::new(decay);
+        let mut control = BasicAggregator::new(decay);
+
+        columnar.observe_many(&timestamps, &values);
+
+        for item in timestamps.into_iter().zip(values) {
+            control.update(item);
+        }
+
+        assert_eq!(columnar.sum(now), control.sum(now));
+        assert_eq!(columnar.count(now), control.count(now));
+        assert_eq!(columnar.stats(), control.stats());
+        assert_eq!(columnar.sum(now), 9.67);
+    }
+
+    #[test]
+    #[should_panic(expected = "given 2 timestamps and 1 values")]
+    fn observe_many_mismatched() {
+        let landmark = Instant::now();
+        let mut aggregator = BasicAggregator::<_, (Instant, f64)>::new(ForwardDecay::new(landmark, ()));
+
+        aggregator.observe_many(&[landmark, landmark], &[1.0]);
+    }
+
+    #[test]
+    fn reset_preserving_last() {
+        let landmark = Instant::now();
+        let new_landmark = landmark + Duration::from_secs(10);
+        let mut gauge = BasicAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.1)));
+
+        gauge.update((landmark + Duration::from_secs(3), 7.0));
+        gauge.update((landmark + Duration::from_secs(8), 42.0));
+        gauge.update((landmark + Duration::from_secs(5), 1.0));
+
+        assert_eq!(gauge.last_value(), Some(42.0));
+
+        let mut dropped = gauge;
+
+        gauge.reset_preserving_last(new_landmark);
+        dropped.reset(new_landmark);
+
+        assert_eq!(gauge.average(), 42.0);
+        assert_eq!(gauge.sum(new_landmark), 42.0);
+        assert_eq!(gauge.last_value(), Some(42.0));
+        assert_eq!(gauge.stats().processed, 0);
+        assert_eq!(dropped.last_value(), None);
+        assert!(dropped.average().is_nan());
+
+        // The carried value persists across consecutive resets, and decays as new items arrive.
+        gauge.reset_preserving_last(new_landmark + Duration::from_secs(10));
+        gauge.update((new_landmark + Duration::from_secs(15), 2.0));
+
+        assert!(gauge.average() > 2.0 && gauge.average() < 42.0);
+        assert_eq!(gauge.last_value(), Some(2.0));
+    }
+
+    #[test]
+    fn query_context() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let context = QueryContext::new(&decay, now);
+        let mut sums = BasicAggregator::new(decay);
+        let mut counts = BasicAggregator::new(decay);
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            sums.update((landmark + Duration::from_secs(offset), value));
+            counts.update((landmark + Duration::from_secs(offset), 1.0));
+        }
+
+        assert_eq!(context.normalizing_factor(), 100.0);
+        assert_eq!(sums.sum_with(&context), Ok(sums.sum(now)));
+        assert_eq!(counts.count_with(&context), Ok(counts.count(now)));
+        assert_eq!(counts.count_with(&context), Ok(1.63));
+
+        let moved = BasicAggregator::<_, (Instant, f64)>::new(ForwardDecay::new(landmark + Duration::from_secs(1), g::Polynomial::new(2)));
+        let other = BasicAggregator::<_, (Instant, f64)>::new(ForwardDecay::new(landmark, g::Polynomial::new(3)));
+
+        assert_eq!(moved.sum_with(&context), Err(Error::LandmarkMismatch));
+        assert_eq!(other.count_with(&context), Err(Error::DecayMismatch));
+    }
+
+    /// Every ordering of the items, by Heap's algorithm.
+    fn permutations<T: Clone>(mut items: Vec<T>) -> Vec<Vec<T>> {
+        let mut permutations = vec![items.clone()];
+        let mut counters = vec![0; items.len()];
+        let mut i = 1;
+
+        while i < items.len() {
+            if counters[i] < i {
+                items.swap(if i % 2 == 0 { 0 } else { counters[i] }, i);
+                permutations.push(items.clone());
+                counters[i] += 1;
+                i = 1;
+            } else {
+                counters[i] = 0;
+                i += 1;
+            }
+        }
+
+        permutations
+    }
+
+    #[test]
+    fn example_order_independent() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let stream: Vec<(Instant, f64)> = [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)].into_iter()
+            .map(|(offset, value)| (landmark + Duration::from_secs(offset), value))
+            .collect();
+        let orders = permutations(stream);
+
+        assert_eq!(orders.len(), 120);
+
+        for order in orders {
+            let mut naive = BasicAggregator::new(decay);
+            let mut kahan = BasicAggregator::<_, _, Kahan>::with_accumulator(decay);
+            let (mut left, mut right) = (naive, naive);
+            let mut quantile = QuantileAggregator::new(decay);
+
+            for (index, item) in order.iter().enumerate() {
+                naive.update(*item);
+                kahan.update(*item);
+                quantile.update(*item);
+
+                if index < 2 { left.update(*item) } else { right.update(*item) }
+            }
+
+            left.merge(&right).unwrap();
+
+            for aggregator in [naive, left] {
+                assert_eq!(aggregator.sum(now), 9.67);
+                assert_eq!(aggregator.count(now), 1.63);
+                assert_eq!(aggregator.static_sum(), 967.0);
+            }
+
+            assert_eq!(kahan.sum(now), 9.67);
+            assert_eq!(kahan.count(now), 1.63);
+            assert_eq!(quantile.quantile(0.5, now), 6.0);
+        }
+    }
+
+    #[test]
+    fn into_results() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let aggregator = [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)].into_iter()
+            .map(|(offset, value)| (landmark + Duration::from_secs(offset), value))
+            .fold(BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2))), |mut aggregator, item| {
+                aggregator.update(item);
+                aggregator
+            });
+        let (sum, count, average) = (aggregator.sum(now), aggregator.count(now), aggregator.average());
+        let results = aggregator.into_results(now);
+
+        assert_eq!(results, Snapshot { sum, count, average });
+        assert_eq!(results.sum, 9.67);
+    }
+
+    #[test]
+    fn defer_landmark() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.1)));
+
+        for offset in 0..50 {
+            aggregator.update((landmark + Duration::from_secs(offset), 1.0 + offset as f64));
+        }
+
+        let mut single = aggregator;
+        let mut repeated = aggregator;
+        let target = landmark + Duration::from_secs(45);
+
+        for offset in (1..=45).rev() {
+            aggregator.defer_landmark(landmark + Duration::from_secs(offset));
+        }
+
+        aggregator.defer_landmark(target);
+
+        for offset in 1..=45 {
+            repeated.update_landmark(landmark + Duration::from_secs(offset));
+        }
+
+        single.update_landmark(target);
+
+        assert_eq!(aggregator.sum(now), single.sum(now));
+        assert_eq!(aggregator.pending_landmark(), Some(target));
+        assert!(aggregator.flush_landmark());
+        assert!(!aggregator.flush_landmark());
+        assert_eq!(aggregator.static_state().landmark, target);
+        assert_eq!(aggregator.static_state(), single.static_state());
+
+        let epsilon = 1e-9;
+
+        assert!((aggregator.sum(now) - single.sum(now)).abs() < epsilon);
+        assert!((repeated.sum(now) - single.sum(now)).abs() < epsilon);
+    }
+
+    #[test]
+    fn normalization() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        aggregator.update((landmark + Duration::from_secs(4), 3.0));
+        aggregator.update((landmark + Duration::from_secs(6), 2.0));
+
+        assert_eq!(aggregator.sum_as(now, Normalization::Static), 24.0);
+        assert_eq!(aggregator.count_as(now, Normalization::Static), 10.0);
+        assert_eq!(aggregator.sum_as(now, Normalization::Decayed), 2.4);
+        assert_eq!(aggregator.count_as(now, Normalization::Decayed), 1.0);
+
+        // Static values ignore the query time, while decayed values keep decaying.
+        let later = now + Duration::from_secs(10);
+
+        assert_eq!(aggregator.sum_as(later, Normalization::Static), 24.0);
+        assert_eq!(aggregator.count_as(later, Normalization::Static), 10.0);
+        assert_eq!(aggregator.sum_as(later, Normalization::Decayed), 1.2);
+        assert_eq!(aggregator.count_as(later, Normalization::Decayed), 0.5);
+    }
+
+    #[test]
+    fn from_parts() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(0.1));
+        let mut aggregator = BasicAggregator::new(decay);
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let state = aggregator.static_state();
+        let restored: BasicAggregator<_, (Instant, f64)> = BasicAggregator::from_parts(decay, state.sum, state.count);
+
+        assert_eq!(restored.static_state(), state);
+        assert_eq!(restored.sum(now), aggregator.sum(now));
+        assert_eq!(restored.count(now), aggregator.count(now));
+        assert_eq!(restored.average(), aggregator.average());
+
+        let compensated: BasicAggregator<_, (Instant, f64), Kahan> = BasicAggregator::from_parts(decay, state.sum, state.count);
+
+        assert_eq!(compensated.static_state(), state);
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+use crate::{Error, ForwardDecay, Item};
+use crate::aggregate::{Aggregator, Checkpoint, CheckpointData};
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// Decayed aggregate sum and count over a stream of items, grouped into fixed-width time buckets.
+/// Retaining the buckets allows queries over a sub-window of the history, at the cost of memory proportional to the number of buckets.
+///
+/// Buckets are aligned to the landmark the aggregator was created or last reset with,
+/// so moving the landmark of an exponential decay does not move the bucket boundaries.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::ForwardDecay;
+/// use fermentation::aggregate::{BucketedAggregator, Aggregator};
+///
+/// let landmark = Instant::now();
+/// let now = landmark + Duration::from_secs(30);
+/// let mut aggregator = BucketedAggregator::new(ForwardDecay::new(landmark, ()), Duration::from_secs(10));
+///
+/// aggregator.update((landmark + Duration::from_secs(5), 1.0));
+/// aggregator.update((landmark + Duration::from_secs(15), 2.0));
+/// aggregator.update((landmark + Duration::from_secs(25), 4.0));
+///
+/// assert_eq!(aggregator.sum(now), 7.0);
+/// assert_eq!(aggregator.sum_in_range(Duration::from_secs(10), Duration::from_secs(30), now), 3.0);
+/// ```
+#[derive(Clone)]
+pub struct BucketedAggregator<G, I> {
+    decay: ForwardDecay<G>,
+    origin: Instant,
+    width: Duration,
+    buckets: BTreeMap<i64, (f64, f64)>,
+    _phantom_data: PhantomData<I>
+}
+
+impl<G, I> Aggregator for BucketedAggregator<G, I> where G: Function, I: Item {
+    type Item = I;
+    /// The decayed sum over every bucket.
+    type Output = f64;
+
+    fn update(&mut self, item: I) {
+        let value = item.value();
+
+        if value.is_nan() {
+            return;
+        }
+
+        let static_weight = self.decay.static_weight(&item);
+        let index = (item.age(self.origin) / self.width.as_secs_f64()).floor() as i64;
+        let (sum, count) = self.buckets.entry(index).or_default();
+
+        *sum += static_weight * value;
+        *count += static_weight;
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+        self.origin = landmark;
+        self.buckets.clear();
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.sum(timestamp)
+    }
+}
+
+impl<I> BucketedAggregator<Exponential, I>
+where
+    I: Item,
+{
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        for (sum, count) in self.buckets.values_mut() {
+            *sum /= factor;
+            *count /= factor;
+        }
+    }
+}
+
+impl<I> SyncLandmark for BucketedAggregator<Exponential, I>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G, I> BucketedAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    /// Initializes a new aggregator with buckets of the given width.
+    ///
+    /// ## Panic
+    /// Panics when the width is zero.
+    pub fn new(decay: ForwardDecay<G>, width: Duration) -> Self {
+        if width.is_zero() {
+            panic!("bucket width must be greater than 0");
+        }
+
+        Self {
+            origin: decay.landmark(),
+            decay,
+            width,
+            buckets: BTreeMap::new(),
+            _phantom_data: Default::default()
+        }
+    }
+
+    /// The decayed sum over every bucket.
+    #[must_use]
+    pub fn sum(&self, timestamp: Instant) -> f64 {
+        self.buckets.values().map(|(sum, _)| sum).sum::<f64>() / self.decay.normalizing_factor(timestamp)
+    }
+
+    /// The decayed sum over every bucket, or [None] when it is not finite.
+    /// Queries divide by the normalizing factor g(t − L), which is 0 for polynomial decay at the landmark
+    /// and overflows to infinity for exponential decay far from the landmark.
+    #[must_use]
+    pub fn sum_finite(&self, timestamp: Instant) -> Option<f64> {
+        Some(self.sum(timestamp)).filter(|sum| sum.is_finite())
+    }
+
+    /// The decayed count over every bucket.
+    #[must_use]
+    pub fn count(&self, timestamp: Instant) -> f64 {
+        self.buckets.values().map(|(_, count)| count).sum::<f64>() / self.decay.normalizing_factor(timestamp)
+    }
+
+    /// The decayed sum over the buckets of items whose age at the given time is between the minimum and maximum ages.
+    ///
+    /// Buckets are included or excluded whole: only buckets whose entire span of ages lies within the range are included.
+    /// The result is exact when both ends of the range fall on bucket boundaries at the query time,
+    /// and otherwise leaves out the items of the partially covered buckets at either end.
+    #[must_use]
+    pub fn sum_in_range(&self, min_age: Duration, max_age: Duration, timestamp: Instant) -> f64 {
+        let width = self.width.as_secs_f64();
+        let age = timestamp.age(self.origin);
+        let (min_age, max_age) = (min_age.as_secs_f64(), max_age.as_secs_f64());
+
+        let sum: f64 = self.buckets.iter()
+            .filter(|(index, _)| {
+                let oldest = age - **index as f64 * width;
+                let youngest = oldest - width;
+
+                youngest >= min_age && oldest <= max_age
+            })
+            .map(|(_, (sum, _))| sum)
+            .sum();
+
+        sum / self.decay.normalizing_factor(timestamp)
+    }
+
+    /// The number of non-empty buckets.
+    pub fn buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn width(&self) -> Duration {
+        self.width
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+}
+
+impl<G, I> Checkpoint<G> for BucketedAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    /// Captures the age in seconds of the bucket origin at the reference time and the bucket width in seconds,
+    /// then the index, static sum and static count of each bucket.
+    fn checkpoint(&self, reference: Instant) -> CheckpointData {
+        let origin = CheckpointData::new(self.origin, reference, Vec::new());
+        let values = [origin.landmark_age, self.width.as_secs_f64()].into_iter()
+            .chain(self.buckets.iter().flat_map(|(index, (sum, count))| [*index as f64, *sum, *count]))
+            .collect();
+
+        CheckpointData::new(self.decay.landmark(), reference, values)
+    }
+
+    fn restore(data: &CheckpointData, mut decay: ForwardDecay<G>, reference: Instant) -> Result<Self, Error> {
+        let [origin_age, width] = data.values.get(..2)
+            .and_then(|header| <[f64; 2]>::try_from(header).ok())
+            .ok_or(Error::InvalidCheckpoint)?;
+        let buckets = &data.values[2..];
+        let origin = CheckpointData { landmark_age: origin_age, values: Vec::new() }.landmark(reference)?;
+        let width = Duration::try_from_secs_f64(width).map_err(|_| Error::InvalidCheckpoint)?;
+
+        if !buckets.len().is_multiple_of(3) || width.is_zero() {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        decay.set_landmark(data.landmark(reference)?);
+
+        let mut aggregator = Self::new(decay, width);
+
+        aggregator.origin = origin;
+        aggregator.buckets = buckets.chunks_exact(3)
+            .map(|bucket| (bucket[0] as i64, (bucket[1], bucket[2])))
+            .collect();
+
+        Ok(aggregator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn sum_in_range() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(0.05));
+        let mut aggregator = BucketedAggregator::new(decay, Duration::from_secs(10));
+        let stream: Vec<(Instant, f64)> = (0..100)
+            .map(|offset| (landmark + Duration::from_secs(offset), (offset % 7) as f64))
+            .collect();
+
+        for item in stream.iter() {
+            aggregator.update(*item);
+        }
+
+        let expected: f64 = stream.iter()
+            .filter(|(timestamp, _)| {
+                let age = (now - *timestamp).as_secs_f64();
+
+                age > 20.0 && age <= 60.0
+            })
+            .map(|item| decay.weighted_value(item, now))
+            .sum();
+        let total: f64 = stream.iter().map(|item| decay.weighted_value(item, now)).sum();
+        let epsilon = 1e-9;
+
+        assert_eq!(aggregator.buckets(), 10);
+        assert!((aggregator.sum_in_range(Duration::from_secs(20), Duration::from_secs(60), now) - expected).abs() < epsilon);
+        assert!((aggregator.sum(now) - total).abs() < epsilon);
+
+        // A range that splits buckets leaves out the partially covered buckets.
+        assert!(aggregator.sum_in_range(Duration::from_secs(25), Duration::from_secs(60), now) < expected);
+    }
+
+    #[test]
+    fn update_landmark() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let mut aggregator = BucketedAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.05)), Duration::from_secs(10));
+        let mut control = aggregator.clone();
+
+        for offset in 0..50 {
+            aggregator.update((landmark + Duration::from_secs(offset), 1.0));
+            control.update((landmark + Duration::from_secs(offset), 1.0));
+        }
+
+        aggregator.update_landmark(landmark + Duration::from_secs(33));
+
+        let (min_age, max_age) = (Duration::from_secs(60), Duration::from_secs(80));
+        let epsilon = 1e-9;
+
+        assert!((aggregator.sum_in_range(min_age, max_age, now) - control.sum_in_range(min_age, max_age, now)).abs() < epsilon);
+        assert!((aggregator.count(now) - control.count(now)).abs() < epsilon);
+    }
+
+    #[test]
+    fn sum_finite() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = BucketedAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)), Duration::from_secs(5));
+
+        aggregator.update((now, 1.0));
+
+        assert_eq!(aggregator.sum_finite(now), Some(1.0));
+        assert_eq!(aggregator.sum_finite(landmark), None);
+        assert_eq!(aggregator.query_finite(landmark), None);
+    }
+}
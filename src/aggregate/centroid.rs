@@ -0,0 +1,201 @@
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+use crate::{ForwardDecay, Item};
+use crate::aggregate::Aggregator;
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// The decayed center of mass of a stream of items in time, i.e. the decayed-weight-weighted mean of their timestamps.
+/// Indicates how fresh an aggregate is on average: a centroid age near 0 means most of the weight comes from recent items.
+///
+/// The aggregator tracks Σw and Σw × (ti − L), the static weights and weighted offsets of the items from the landmark.
+/// Their ratio is the mean offset of the items, which does not depend on the query time,
+/// while the age of the centroid grows with the query time. Only timestamps are aggregated, so values (i.e. NaN) are ignored.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{CentroidAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), ());
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let mut aggregator = CentroidAggregator::new(decay);
+///
+/// aggregator.update(landmark + Duration::from_secs(2));
+/// aggregator.update(landmark + Duration::from_secs(6));
+///
+/// assert_eq!(aggregator.centroid_age(now), 6.0);
+/// assert_eq!(aggregator.centroid(), Some(landmark + Duration::from_secs(4)));
+/// ```
+#[derive(Copy, Clone)]
+pub struct CentroidAggregator<G, I> {
+    decay: ForwardDecay<G>,
+    count: f64,
+    offset: f64,
+    _phantom_data: PhantomData<I>
+}
+
+impl<G, I> Aggregator for CentroidAggregator<G, I> where G: Function, I: Item {
+    type Item = I;
+    /// The age of the centroid at the query time.
+    type Output = f64;
+
+    fn update(&mut self, item: I) {
+        let static_weight = self.decay.static_weight(&item);
+
+        self.count += static_weight;
+        self.offset += static_weight * item.age(self.decay.landmark());
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+        self.count = 0.0;
+        self.offset = 0.0;
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.centroid_age(timestamp)
+    }
+}
+
+impl<I> CentroidAggregator<Exponential, I>
+where
+    I: Item,
+{
+    /// Moves the landmark, rescaling the static weights and shifting the offsets by the distance between the landmarks.
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let shift = landmark.age(self.decay.landmark());
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        self.offset = (self.offset - shift * self.count) / factor;
+        self.count /= factor;
+    }
+}
+
+impl<I> SyncLandmark for CentroidAggregator<Exponential, I>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G, I> CentroidAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    pub fn new(decay: ForwardDecay<G>) -> Self {
+        Self {
+            decay,
+            count: 0.0,
+            offset: 0.0,
+            _phantom_data: Default::default()
+        }
+    }
+
+    /// The weighted mean offset of the items from the landmark in seconds, or NaN when no items have been aggregated.
+    /// The normalizing factor cancels out of the ratio, so the mean offset needs no query time.
+    #[must_use]
+    pub fn mean_offset(&self) -> f64 {
+        self.offset / self.count
+    }
+
+    /// The weighted mean age of the items at the given time in seconds, or NaN when no items have been aggregated.
+    #[must_use]
+    pub fn centroid_age(&self, timestamp: Instant) -> f64 {
+        self.decay.landmark_age_at(timestamp) - self.mean_offset()
+    }
+
+    /// The weighted mean timestamp of the items, or [None] when no items have been aggregated
+    /// or the mean is not representable as an [Instant].
+    #[must_use]
+    pub fn centroid(&self) -> Option<Instant> {
+        let offset = self.mean_offset();
+        let landmark = self.decay.landmark();
+        let duration = Duration::try_from_secs_f64(offset.abs()).ok()?;
+
+        if offset >= 0.0 {
+            landmark.checked_add(duration)
+        } else {
+            landmark.checked_sub(duration)
+        }
+    }
+
+    /// The decayed count of items.
+    #[must_use]
+    pub fn count(&self, timestamp: Instant) -> f64 {
+        self.count / self.decay.normalizing_factor(timestamp)
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn recent_burst() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let mut aggregator = CentroidAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.05)));
+
+        for offset in 0..100 {
+            aggregator.update(landmark + Duration::from_secs(offset));
+        }
+
+        let steady = aggregator.centroid_age(now);
+
+        for _ in 0..100 {
+            aggregator.update(now);
+        }
+
+        let burst = aggregator.centroid_age(now);
+
+        assert!(steady > 10.0, "expected a steady centroid age above 10, got {steady}");
+        assert!(burst < steady / 2.0, "expected the burst to pull the centroid age below {}, got {burst}", steady / 2.0);
+        assert!(burst > 0.0);
+    }
+
+    #[test]
+    fn update_landmark() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(60);
+        let mut aggregator = CentroidAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.1)));
+
+        for offset in 1..=50 {
+            aggregator.update(landmark + Duration::from_secs(offset));
+        }
+
+        let control = aggregator;
+
+        aggregator.update_landmark(landmark + Duration::from_secs(30));
+
+        let epsilon = 1e-9;
+
+        assert!((aggregator.centroid_age(now) - control.centroid_age(now)).abs() < epsilon);
+        assert!((aggregator.count(now) - control.count(now)).abs() < epsilon);
+    }
+
+    #[test]
+    fn empty() {
+        let landmark = Instant::now();
+        let aggregator: CentroidAggregator<_, Instant> = CentroidAggregator::new(ForwardDecay::new(landmark, ()));
+
+        assert!(aggregator.centroid_age(landmark).is_nan());
+        assert_eq!(aggregator.centroid(), None);
+    }
+}
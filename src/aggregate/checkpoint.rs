@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+use crate::{Error, ForwardDecay};
+
+/// The essential state of an aggregator, rebased to a reference time so it remains meaningful across process restarts.
+///
+/// An [Instant] is only comparable within the process that created it,
+/// so the landmark is stored as its age in seconds at the reference time instead.
+/// The static (not normalized) accumulators are relative to the landmark, so they are stored as-is.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckpointData {
+    /// The age of the landmark at the reference time, in seconds. Negative when the landmark is after the reference time.
+    pub landmark_age: f64,
+    /// The static accumulators, in an order specific to each aggregator.
+    pub values: Vec<f64>,
+}
+
+/// Aggregators whose state can be captured in a [CheckpointData] and restored later, possibly in another process.
+///
+/// Checkpoints only capture the static accumulators, not options such as policies or diagnostic counters,
+/// which are expected to come from the configuration of the restoring process.
+/// Aggregators that retain items or keys (i.e. [MinMaxAggregator](crate::aggregate::MinMaxAggregator))
+/// have no portable representation and do not implement this trait.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{Aggregator, BasicAggregator, Checkpoint};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let mut aggregator = BasicAggregator::new(decay);
+///
+/// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+///
+/// let data = aggregator.checkpoint(now);
+///
+/// // In a new process, the reference time is the instant that corresponds to the same wall-clock time.
+/// let later = Instant::now();
+/// let restored: BasicAggregator<_, (Instant, f64)> = BasicAggregator::restore(&data, ForwardDecay::new(later, g::Polynomial::new(2)), later).unwrap();
+///
+/// assert_eq!(restored.static_state().landmark + Duration::from_secs(10), later);
+/// assert_eq!(restored.sum(later), 1.0);
+/// ```
+pub trait Checkpoint<G>: Sized {
+    /// Captures the state of this aggregator, with the landmark rebased to the given reference time.
+    fn checkpoint(&self, reference: Instant) -> CheckpointData;
+
+    /// Restores an aggregator from a checkpoint, given the decay function to use
+    /// and the reference time corresponding to the reference of the checkpoint.
+    /// The landmark of the given decay is replaced by the one recorded in the checkpoint.
+    ///
+    /// Returns [Error::InvalidCheckpoint] when the checkpoint does not hold the state of this kind of aggregator.
+    fn restore(data: &CheckpointData, decay: ForwardDecay<G>, reference: Instant) -> Result<Self, Error>;
+}
+
+impl CheckpointData {
+    pub(crate) fn new(landmark: Instant, reference: Instant, values: Vec<f64>) -> Self {
+        Self {
+            landmark_age: reference.duration_since(landmark).as_secs_f64() - landmark.duration_since(reference).as_secs_f64(),
+            values,
+        }
+    }
+
+    /// The landmark corresponding to the recorded age at the given reference time.
+    pub(crate) fn landmark(&self, reference: Instant) -> Result<Instant, Error> {
+        let age = Duration::try_from_secs_f64(self.landmark_age.abs()).map_err(|_| Error::InvalidCheckpoint)?;
+
+        if self.landmark_age >= 0.0 {
+            reference.checked_sub(age)
+        } else {
+            reference.checked_add(age)
+        }
+        .ok_or(Error::InvalidCheckpoint)
+    }
+
+    /// The values, if there are exactly the expected number of them.
+    pub(crate) fn values<const N: usize>(&self) -> Result<[f64; N], Error> {
+        self.values.as_slice().try_into().map_err(|_| Error::InvalidCheckpoint)
+    }
+}
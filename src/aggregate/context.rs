@@ -0,0 +1,79 @@
+use std::time::Instant;
+use crate::{Error, ForwardDecay};
+use crate::g::Function;
+
+/// A query time shared by several aggregators with the same decay model,
+/// which computes the normalizing factor g(t − L) once for all of them.
+///
+/// The static aggregates are only comparable relative to the same landmark and decay function,
+/// so queries through a context return [Error::LandmarkMismatch] or [Error::DecayMismatch] for an aggregator
+/// whose decay model differs from the one the context was built from, i.e. after the aggregator's landmark moved.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{Aggregator, BasicAggregator, QueryContext};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let landmark = decay.landmark();
+/// let context = QueryContext::new(&decay, landmark + Duration::from_secs(10));
+/// let mut aggregator = BasicAggregator::new(decay);
+///
+/// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+///
+/// assert_eq!(aggregator.sum_with(&context), Ok(1.0));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct QueryContext<G> {
+    decay: ForwardDecay<G>,
+    timestamp: Instant,
+    factor: f64,
+}
+
+impl<G> QueryContext<G>
+where
+    G: Function,
+{
+    /// A context for queries at the given time against aggregators with the given decay model.
+    pub fn new(decay: &ForwardDecay<G>, timestamp: Instant) -> Self
+    where
+        G: Clone,
+    {
+        Self {
+            decay: decay.clone(),
+            timestamp,
+            factor: decay.normalizing_factor(timestamp),
+        }
+    }
+
+    pub fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
+
+    pub fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    /// The normalizing factor g(t − L) shared by every query through this context.
+    pub fn normalizing_factor(&self) -> f64 {
+        self.factor
+    }
+
+    /// The normalizing factor for an aggregator with the given decay model,
+    /// if the model matches the one this context was built from.
+    pub(crate) fn factor_for(&self, decay: &ForwardDecay<G>) -> Result<f64, Error>
+    where
+        G: PartialEq,
+    {
+        if self.decay.landmark() != decay.landmark() {
+            return Err(Error::LandmarkMismatch);
+        }
+
+        if self.decay.g() != decay.g() || self.decay.time_scale() != decay.time_scale() {
+            return Err(Error::DecayMismatch);
+        }
+
+        Ok(self.factor)
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+use std::time::Instant;
+use crate::{Item, WeightedItem};
+use crate::aggregate::{Aggregator, BasicAggregator};
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// A [BasicAggregator] that skips duplicate deliveries of the same logical event,
+/// as produced by at-least-once delivery.
+///
+/// Each item is identified by a key from a user-supplied extractor.
+/// Only the keys of the most recently aggregated items are remembered, up to the given capacity,
+/// so memory is bounded by the capacity regardless of the length of the stream.
+/// A duplicate is therefore only detected when it arrives within `capacity` distinct items of the original;
+/// a redelivery after the original's key was evicted is aggregated again.
+/// Skipped duplicates do not refresh the position of their key.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{BasicAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// // Events are identified by their timestamp.
+/// let mut aggregator = BasicAggregator::new(decay).dedup_by(16, |item: &(Instant, f64)| item.0);
+///
+/// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+/// aggregator.update((landmark + Duration::from_secs(7), 8.0));
+/// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+///
+/// assert_eq!(aggregator.duplicates(), 1);
+/// assert_eq!(aggregator.aggregator().sum(now), 4.0 * 0.25 + 8.0 * 0.49);
+/// ```
+#[derive(Clone)]
+pub struct DedupAggregator<G, I, K, F> {
+    aggregator: BasicAggregator<G, I>,
+    id: F,
+    capacity: usize,
+    recent: VecDeque<K>,
+    seen: HashSet<K>,
+    duplicates: u64,
+}
+
+impl<G, I, K, F> Aggregator for DedupAggregator<G, I, K, F>
+where
+    G: Function,
+    I: WeightedItem,
+    K: Eq + Hash + Clone,
+    F: Fn(&I) -> K,
+{
+    type Item = I;
+    /// The decayed sum over the distinct items.
+    type Output = f64;
+
+    fn update(&mut self, item: I) {
+        let key = (self.id)(&item);
+
+        if self.seen.contains(&key) {
+            self.duplicates += 1;
+            return;
+        }
+
+        if self.recent.len() == self.capacity {
+            if let Some(evicted) = self.recent.pop_front() {
+                self.seen.remove(&evicted);
+            }
+        }
+
+        self.recent.push_back(key.clone());
+        self.seen.insert(key);
+        self.aggregator.update(item);
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.aggregator.reset(landmark);
+        self.recent.clear();
+        self.seen.clear();
+        self.duplicates = 0;
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.aggregator.sum(timestamp)
+    }
+}
+
+impl<I, K, F> DedupAggregator<Exponential, I, K, F>
+where
+    I: Item,
+{
+    /// Moves the landmark without forgetting the recently seen keys.
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        self.aggregator.update_landmark(landmark);
+    }
+}
+
+impl<I, K, F> SyncLandmark for DedupAggregator<Exponential, I, K, F>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.aggregator.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G, I, K, F> DedupAggregator<G, I, K, F>
+where
+    G: Function,
+    I: Item,
+    K: Eq + Hash,
+    F: Fn(&I) -> K,
+{
+    /// Wraps the given aggregator, remembering the keys of up to the given number of recent items.
+    ///
+    /// ## Panic
+    /// Panics when the capacity is 0.
+    pub fn new(aggregator: BasicAggregator<G, I>, capacity: usize, id: F) -> Self {
+        if capacity == 0 {
+            panic!("capacity must be greater than 0");
+        }
+
+        Self {
+            aggregator,
+            id,
+            capacity,
+            recent: VecDeque::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+            duplicates: 0,
+        }
+    }
+
+    /// The decayed aggregates over every distinct item since creation or the last reset.
+    pub fn aggregator(&self) -> &BasicAggregator<G, I> {
+        &self.aggregator
+    }
+
+    /// The number of items skipped as duplicates since creation or the last reset.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// The maximum number of recent keys remembered for detecting duplicates.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::{g, ForwardDecay};
+    use super::*;
+
+    #[test]
+    fn counts_once() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(decay).dedup_by(8, |item: &(Instant, f64)| item.0);
+        let mut control = BasicAggregator::new(decay);
+        let stream = [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)];
+
+        for _ in 0..3 {
+            for (offset, value) in stream {
+                aggregator.update((landmark + Duration::from_secs(offset), value));
+            }
+        }
+
+        for (offset, value) in stream {
+            control.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        assert_eq!(aggregator.duplicates(), 10);
+        assert_eq!(aggregator.aggregator().sum(now), control.sum(now));
+        assert_eq!(aggregator.aggregator().count(now), control.count(now));
+    }
+
+    #[test]
+    fn bounded_window() {
+        let landmark = Instant::now();
+        let decay = ForwardDecay::new(landmark, ());
+        let mut aggregator = DedupAggregator::new(BasicAggregator::new(decay), 2, |item: &(Instant, f64)| item.1 as u64);
+
+        aggregator.update((landmark, 1.0));
+        aggregator.update((landmark, 2.0));
+        aggregator.update((landmark, 1.0));
+        aggregator.update((landmark, 3.0));
+        // The key of the first item was evicted by the third distinct item.
+        aggregator.update((landmark, 1.0));
+
+        assert_eq!(aggregator.duplicates(), 1);
+        assert_eq!(aggregator.aggregator().static_sum(), 7.0);
+
+        aggregator.reset(landmark);
+        aggregator.update((landmark, 3.0));
+
+        assert_eq!(aggregator.duplicates(), 0);
+        assert_eq!(aggregator.aggregator().static_sum(), 3.0);
+    }
+}
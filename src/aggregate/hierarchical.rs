@@ -0,0 +1,196 @@
+use std::time::Instant;
+use crate::{ForwardDecay, Item};
+use crate::aggregate::{Aggregator, QuantileAggregator};
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// Approximate decayed quantiles over a stream of items, combined from several child [QuantileAggregator]s.
+///
+/// Items are distributed round-robin across the children, so each child aggregates an interleaved sample of the stream.
+/// A quantile is estimated as the weighted quantile of the children's estimates, weighted by each child's decayed count;
+/// the median is thus a weighted median of medians.
+///
+/// Each child retains a sorted list of its values, so distributing n items across k children
+/// reduces the cost of an update from O(n) to O(n / k) and the cost of a query to O(n / k + k log k), for the same memory.
+/// In exchange the result is an estimate: each child's quantile is subject to sampling error that shrinks with the number of items per child,
+/// and combining estimates is not the same as the quantile of the union, so skewed or clustered streams may be off by more.
+/// Fewer children give more accurate estimates; more children give faster updates.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{HierarchicalQuantile, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(1));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(101);
+/// let mut aggregator = HierarchicalQuantile::new(decay, 4);
+///
+/// for i in 1..=100 {
+///     aggregator.update((landmark + Duration::from_secs(i), 10.0));
+/// }
+///
+/// assert_eq!(aggregator.median(now), 10.0);
+/// ```
+#[derive(Clone)]
+pub struct HierarchicalQuantile<G, I> {
+    children: Vec<QuantileAggregator<G, I>>,
+    next: usize,
+}
+
+impl<G, I> Aggregator for HierarchicalQuantile<G, I> where G: Function, I: Item {
+    type Item = I;
+    /// The estimated decayed median.
+    type Output = f64;
+
+    fn update(&mut self, item: I) {
+        self.children[self.next].update(item);
+        self.next = (self.next + 1) % self.children.len();
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        for child in self.children.iter_mut() {
+            child.reset(landmark);
+        }
+
+        self.next = 0;
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.median(timestamp)
+    }
+}
+
+impl<I> HierarchicalQuantile<Exponential, I>
+where
+    I: Item,
+{
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        for child in self.children.iter_mut() {
+            child.update_landmark(landmark);
+        }
+    }
+}
+
+impl<I> SyncLandmark for HierarchicalQuantile<Exponential, I>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.children[0].landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G, I> HierarchicalQuantile<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    /// Initializes a new aggregator with the given number of children, each sharing the decay model.
+    ///
+    /// ## Panic
+    /// Panics when the number of children is 0.
+    pub fn new(decay: ForwardDecay<G>, children: usize) -> Self
+    where
+        G: Clone,
+    {
+        if children == 0 {
+            panic!("children must be greater than 0");
+        }
+
+        Self {
+            children: (0..children).map(|_| QuantileAggregator::new(decay.clone())).collect(),
+            next: 0,
+        }
+    }
+
+    /// The estimated decayed φ-quantile at the given time, or NaN when no items have been aggregated.
+    #[must_use]
+    pub fn quantile(&self, phi: f64, timestamp: Instant) -> f64 {
+        let mut estimates: Vec<(f64, f64)> = self.children.iter()
+            .map(|child| (child.quantile(phi, timestamp), child.count(timestamp)))
+            .filter(|(estimate, count)| !estimate.is_nan() && *count > 0.0)
+            .collect();
+
+        estimates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let total: f64 = estimates.iter().map(|(_, count)| count).sum();
+        let mut cumulative = 0.0;
+
+        for (estimate, count) in estimates.iter() {
+            cumulative += count;
+
+            if cumulative >= phi * total {
+                return *estimate;
+            }
+        }
+
+        estimates.last().map_or(f64::NAN, |(estimate, _)| *estimate)
+    }
+
+    /// The estimated decayed φ-quantile, or [None] when it is not finite, i.e. when no items have been aggregated.
+    #[must_use]
+    pub fn quantile_finite(&self, phi: f64, timestamp: Instant) -> Option<f64> {
+        Some(self.quantile(phi, timestamp)).filter(|quantile| quantile.is_finite())
+    }
+
+    /// The estimated decayed median at the given time, or NaN when no items have been aggregated.
+    #[must_use]
+    pub fn median(&self, timestamp: Instant) -> f64 {
+        self.quantile(0.5, timestamp)
+    }
+
+    /// The child aggregators, in the order items are distributed to them.
+    pub fn children(&self) -> &[QuantileAggregator<G, I>] {
+        &self.children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn tracks_median() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(20);
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(0.1));
+        let mut hierarchical = HierarchicalQuantile::new(decay, 8);
+        let mut exact = QuantileAggregator::new(decay);
+
+        for i in 0..20_000u64 {
+            // A drifting signal, so the decayed median differs from the undecayed one.
+            let value = ((i * 7919) % 1000) as f64 + i as f64 / 20.0;
+            let item = (landmark + Duration::from_millis(i), value);
+
+            hierarchical.update(item);
+            exact.update(item);
+        }
+
+        let expected = exact.quantile(0.5, now);
+        let undecayed = 500.0 + 500.0;
+        let epsilon = 10.0;
+
+        assert!((hierarchical.median(now) - expected).abs() < epsilon, "expected {expected}, got {}", hierarchical.median(now));
+        assert!((expected - undecayed).abs() > epsilon);
+        assert!((hierarchical.quantile(0.9, now) - exact.quantile(0.9, now)).abs() < epsilon);
+        assert_eq!(hierarchical.children().len(), 8);
+    }
+
+    #[test]
+    fn empty() {
+        let landmark = Instant::now();
+        let aggregator: HierarchicalQuantile<_, (Instant, f64)> = HierarchicalQuantile::new(ForwardDecay::new(landmark, ()), 2);
+
+        assert!(aggregator.median(landmark).is_nan());
+        assert_eq!(aggregator.quantile_finite(0.5, landmark), None);
+        assert_eq!(aggregator.query_finite(landmark), None);
+    }
+}
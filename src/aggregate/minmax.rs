@@ -1,9 +1,11 @@
+use std::cmp::Ordering;
 use std::mem;
 use std::time::Instant;
 
-use crate::{ForwardDecay, Item};
+use crate::{Error, ForwardDecay, Item};
 use crate::aggregate::Aggregator;
-use crate::g::Function;
+use crate::g::{Exponential, Function, LandmarkWindow};
+use crate::landmark::SyncLandmark;
 
 #[derive(Default)]
 enum MinMax<I> {
@@ -31,6 +33,16 @@ impl<I> MinMax<I> {
     }
 }
 
+/// The raw state of a [MinMaxAggregator]: the retained items along with their static weighted values, relative to the landmark.
+/// Unlike the owned states of aggregators with scalar accumulators, such as [AggregatorState](crate::aggregate::AggregatorState),
+/// the state borrows the retained items, which are generic and need not be [Clone].
+#[derive(Debug, PartialEq)]
+pub struct MinMaxState<'a, I> {
+    pub min: Option<(&'a I, f64)>,
+    pub max: Option<(&'a I, f64)>,
+    pub landmark: Instant,
+}
+
 /// An aggregation computation over a stream of items to determine the decayed min and max.
 ///
 /// ## Example
@@ -64,35 +76,31 @@ impl<I> MinMax<I> {
 /// assert_eq!(aggregator.min(), None);
 /// assert_eq!(aggregator.max(), None);
 /// ```
-pub struct MinMaxAggregator<G, I> {
+pub struct MinMaxAggregator<G, I, F = fn(&I, &I) -> Ordering> {
     decay: ForwardDecay<G>,
     min_max: MinMax<I>,
+    comparator: Option<F>,
 }
 
-impl<G, I> Aggregator for MinMaxAggregator<G, I> where G: Function, I: Item {
+impl<G, I, F> Aggregator for MinMaxAggregator<G, I, F> where G: Function, I: Item, F: Fn(&I, &I) -> Ordering {
     type Item = I;
+    /// The decayed weighted values of the min and max items, if any.
+    type Output = Option<(f64, f64)>;
 
     fn update(&mut self, item: I) {
         self.min_max = match mem::take(&mut self.min_max) {
             MinMax::Neither => MinMax::Same(item),
             MinMax::Same(min_max) => {
-                let min_max_static_weight = self.decay.static_weighted_value(&min_max);
-                let item_static_weight = self.decay.static_weighted_value(&item);
-
-                if min_max_static_weight <= item_static_weight {
+                if matches!(self.compare(&min_max, &item), Some(Ordering::Less | Ordering::Equal)) {
                     MinMax::Both(min_max, item)
                 } else {
                     MinMax::Both(item, min_max)
                 }
             }
             MinMax::Both(min, max) => {
-                let min_static_weight = self.decay.static_weighted_value(&min);
-                let max_static_weight = self.decay.static_weighted_value(&max);
-                let item_static_weight = self.decay.static_weighted_value(&item);
-
-                if item_static_weight < min_static_weight {
+                if self.compare(&item, &min) == Some(Ordering::Less) {
                     MinMax::Both(item, max)
-                } else if item_static_weight > max_static_weight {
+                } else if self.compare(&item, &max) == Some(Ordering::Greater) {
                     MinMax::Both(min, item)
                 } else {
                     MinMax::Both(min, max)
@@ -106,6 +114,112 @@ impl<G, I> Aggregator for MinMaxAggregator<G, I> where G: Function, I: Item {
         self.min_max = MinMax::Neither;
     }
 
+    fn query(&self, timestamp: Instant) -> Option<(f64, f64)> {
+        let (_, min) = self.min_entry(timestamp)?;
+        let (_, max) = self.max_entry(timestamp)?;
+
+        Some((min, max))
+    }
+}
+
+impl<I, F> MinMaxAggregator<Exponential, I, F>
+where
+    I: Item,
+{
+    /// Moves the landmark without changing the retained items.
+    /// Under exponential decay, a new landmark scales every static weighted value by the same factor,
+    /// so the relative order of items, and therefore the min and max, is unchanged.
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+    }
+
+    /// Combines the min and max of another aggregator into this one, as if this aggregator had seen the other's items as well.
+    /// Returns an error, without changing this aggregator, when the decay rates or time scales differ.
+    ///
+    /// Unlike other aggregators, the landmarks may differ. The other's items are compared by their static weighted values
+    /// rebased to this aggregator's landmark: under exponential decay, moving the landmark by d scales every static weight
+    /// by the same factor exp(-αd), so the order of items, and therefore the global min and max, does not depend on the landmark.
+    ///
+    /// The same does not hold for polynomial decay, where static weights (ti − L)^β are not proportional across landmarks:
+    /// an item close to one landmark gains relative weight when the landmark moves back,
+    /// so the extremes retained under one landmark may not be the extremes of the same items under another.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use fermentation::{ForwardDecay, g};
+    /// use fermentation::aggregate::{MinMaxAggregator, Aggregator};
+    ///
+    /// let landmark = Instant::now();
+    /// let mut left = MinMaxAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.1)));
+    /// let mut right = MinMaxAggregator::new(ForwardDecay::new(landmark + Duration::from_secs(60), g::Exponential::new(0.1)));
+    ///
+    /// left.update((landmark + Duration::from_secs(5), 4.0));
+    /// right.update((landmark + Duration::from_secs(65), 1.0));
+    ///
+    /// assert_eq!(left.merge(right), Ok(()));
+    /// assert_eq!(left.min(), Some(&(landmark + Duration::from_secs(5), 4.0)));
+    /// assert_eq!(left.max(), Some(&(landmark + Duration::from_secs(65), 1.0)));
+    /// ```
+    #[must_use = "merging fails when the decay functions differ"]
+    pub fn merge(&mut self, other: Self) -> Result<(), Error>
+    where
+        F: Fn(&I, &I) -> Ordering,
+    {
+        if self.decay.g() != other.decay.g() || self.decay.time_scale() != other.decay.time_scale() {
+            return Err(Error::DecayMismatch);
+        }
+
+        match other.min_max {
+            MinMax::Neither => {}
+            MinMax::Same(min_max) => self.update(min_max),
+            MinMax::Both(min, max) => {
+                self.update(min);
+                self.update(max);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<I, F> SyncLandmark for MinMaxAggregator<Exponential, I, F>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<I, F> MinMaxAggregator<LandmarkWindow, I, F>
+where
+    I: Item,
+    F: Fn(&I, &I) -> Ordering,
+{
+    /// Moves the landmark of a landmark window, re-evaluating the min and max among the given items inside the new window,
+    /// i.e. those after the new landmark.
+    ///
+    /// The aggregator only retains its min and max, so when either drops out of the window the new one cannot be recovered
+    /// without the other items. The given items must therefore include every item after the new landmark,
+    /// such as the items retained by a [RingAggregator](crate::aggregate::RingAggregator) large enough to hold the window.
+    pub fn update_landmark<R>(&mut self, landmark: Instant, retained: R)
+    where
+        R: IntoIterator<Item = I>,
+    {
+        self.decay.set_landmark(landmark);
+        self.min_max = MinMax::Neither;
+
+        for item in retained {
+            if item.age(landmark) > 0.0 {
+                self.update(item);
+            }
+        }
+    }
 }
 
 impl<G, I> MinMaxAggregator<G, I>
@@ -113,24 +227,93 @@ where
     G: Function,
     I: Item,
 {
+    /// Initializes a new aggregator that compares items by their static weighted values.
     pub fn new(decay: ForwardDecay<G>) -> Self {
         Self {
             decay,
             min_max: MinMax::Neither,
+            comparator: None,
         }
     }
+}
 
+impl<G, I, F> MinMaxAggregator<G, I, F>
+where
+    G: Function,
+    I: Item,
+    F: Fn(&I, &I) -> Ordering,
+{
+    /// Initializes a new aggregator that compares items with the given comparator
+    /// instead of by their static weighted values, i.e. by timestamp to track the oldest and newest items.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use fermentation::{ForwardDecay, Item, g};
+    /// use fermentation::aggregate::{MinMaxAggregator, Aggregator};
+    ///
+    /// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+    /// let landmark = decay.landmark();
+    /// let mut aggregator = MinMaxAggregator::by(decay, |a: &(Instant, f64), b: &(Instant, f64)| a.timestamp().cmp(&b.timestamp()));
+    ///
+    /// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+    /// aggregator.update((landmark + Duration::from_secs(7), 1.0));
+    /// aggregator.update((landmark + Duration::from_secs(3), 8.0));
+    ///
+    /// assert_eq!(aggregator.min(), Some(&(landmark + Duration::from_secs(3), 8.0)));
+    /// assert_eq!(aggregator.max(), Some(&(landmark + Duration::from_secs(7), 1.0)));
+    /// ```
+    pub fn by(decay: ForwardDecay<G>, comparator: F) -> Self {
+        Self {
+            decay,
+            min_max: MinMax::Neither,
+            comparator: Some(comparator),
+        }
+    }
+
+    #[must_use]
     pub fn min(&self) -> Option<&I> {
         self.min_max.min()
     }
 
+    #[must_use]
     pub fn max(&self) -> Option<&I> {
         self.min_max.max()
     }
 
+    /// The retained min item along with its decayed weighted value at the given time.
+    #[must_use]
+    pub fn min_entry(&self, timestamp: Instant) -> Option<(&I, f64)> {
+        self.min().map(|item| (item, self.decay.weighted_value(item, timestamp)))
+    }
+
+    /// The retained max item along with its decayed weighted value at the given time.
+    #[must_use]
+    pub fn max_entry(&self, timestamp: Instant) -> Option<(&I, f64)> {
+        self.max().map(|item| (item, self.decay.weighted_value(item, timestamp)))
+    }
+
+    /// The raw state of this aggregator, with static (not normalized) weighted values.
+    pub fn static_state(&self) -> MinMaxState<'_, I> {
+        MinMaxState {
+            min: self.min().map(|item| (item, self.decay.static_weighted_value(item))),
+            max: self.max().map(|item| (item, self.decay.static_weighted_value(item))),
+            landmark: self.decay.landmark(),
+        }
+    }
+
     pub fn decay(&mut self) -> &ForwardDecay<G> {
         &self.decay
     }
+
+    /// Compares items with the comparator, or by static weighted value by default.
+    /// Returns [None] when the default comparison involves NaN.
+    fn compare(&self, a: &I, b: &I) -> Option<Ordering> {
+        match &self.comparator {
+            Some(comparator) => Some(comparator(a, b)),
+            None => self.decay.static_weighted_value(a).partial_cmp(&self.decay.static_weighted_value(b)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -163,4 +346,145 @@ mod tests {
         assert_eq!(aggregator.min(), Some(&(landmark + Duration::from_secs(3), 3.0)));
         assert_eq!(aggregator.max(), Some(&(landmark + Duration::from_secs(7), 8.0)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn entries() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = MinMaxAggregator::new(fd);
+
+        assert_eq!(aggregator.min_entry(now), None);
+        assert_eq!(aggregator.max_entry(now), None);
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        assert_eq!(aggregator.min_entry(now), Some((&(landmark + Duration::from_secs(3), 3.0), 3.0 * 0.09)));
+        assert_eq!(aggregator.max_entry(now), Some((&(landmark + Duration::from_secs(7), 8.0), 8.0 * 0.49)));
+    }
+
+    #[test]
+    fn static_state() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = MinMaxAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let state = aggregator.static_state();
+        let factor = aggregator.decay.normalizing_factor(now);
+        let (min, static_min) = state.min.unwrap();
+        let (max, static_max) = state.max.unwrap();
+
+        assert_eq!(state.landmark, landmark);
+        assert_eq!(aggregator.min_entry(now), Some((min, static_min / factor)));
+        assert_eq!(aggregator.max_entry(now), Some((max, static_max / factor)));
+    }
+
+    #[test]
+    fn update_landmark() {
+        let landmark = Instant::now();
+        let new_landmark = landmark + Duration::from_secs(6);
+        let fd = ForwardDecay::new(landmark, g::Exponential::new(0.2));
+        let mut aggregator = MinMaxAggregator::new(fd);
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        aggregator.update_landmark(new_landmark);
+        aggregator.update((landmark + Duration::from_secs(8), 6.0));
+        aggregator.update((landmark + Duration::from_secs(4), 4.0));
+
+        assert_eq!(aggregator.decay().landmark(), new_landmark);
+        assert_eq!(aggregator.min(), Some(&(landmark + Duration::from_secs(3), 3.0)));
+        assert_eq!(aggregator.max(), Some(&(landmark + Duration::from_secs(7), 8.0)));
+    }
+
+    #[test]
+    fn by_timestamp() {
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = MinMaxAggregator::by(fd, |a: &(Instant, f64), b: &(Instant, f64)| a.0.cmp(&b.0));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 1.0), (4, 9.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        assert_eq!(aggregator.min(), Some(&(landmark + Duration::from_secs(3), 3.0)));
+        assert_eq!(aggregator.max(), Some(&(landmark + Duration::from_secs(8), 1.0)));
+    }
+
+    #[test]
+    fn landmark_window() {
+        let landmark = Instant::now();
+        let new_landmark = landmark + Duration::from_secs(5);
+        let stream: Vec<(Instant, f64)> = [9.0, 1.0, 4.0, 7.0, 2.0, 6.0, 3.0, 5.0].into_iter()
+            .enumerate()
+            .map(|(index, value)| (landmark + Duration::from_secs(index as u64 + 1), value))
+            .collect();
+        let mut aggregator = MinMaxAggregator::new(ForwardDecay::new(landmark, g::LandmarkWindow));
+
+        for item in stream.iter() {
+            aggregator.update(*item);
+        }
+
+        assert_eq!(aggregator.min(), Some(&stream[1]));
+        assert_eq!(aggregator.max(), Some(&stream[0]));
+
+        aggregator.update_landmark(new_landmark, stream.iter().copied());
+
+        // Both extremes arrived before the new landmark, so they are replaced by the extremes of the items after it.
+        assert_eq!(aggregator.min(), Some(&stream[6]));
+        assert_eq!(aggregator.max(), Some(&stream[5]));
+        assert_eq!(aggregator.static_state().landmark, new_landmark);
+    }
+
+    #[test]
+    fn merge_landmarks() {
+        let landmark = Instant::now();
+        let later = landmark + Duration::from_secs(30);
+        let stream: Vec<(Instant, f64)> = [(5, 4.0), (7, 8.0), (3, 3.0), (38, 1.0), (34, 2.0), (36, 5.0), (33, 9.0)].into_iter()
+            .map(|(offset, value)| (landmark + Duration::from_secs(offset), value))
+            .collect();
+        let g = g::Exponential::new(0.2);
+        let mut expected = MinMaxAggregator::new(ForwardDecay::new(landmark, g));
+        let mut left = MinMaxAggregator::new(ForwardDecay::new(landmark, g));
+        let mut right = MinMaxAggregator::new(ForwardDecay::new(later, g));
+
+        for (index, item) in stream.iter().enumerate() {
+            expected.update(*item);
+
+            if index < 3 {
+                left.update(*item);
+            } else {
+                right.update(*item);
+            }
+        }
+
+        left.merge(right).unwrap();
+
+        assert_eq!(left.decay().landmark(), landmark);
+        assert_eq!(left.min(), expected.min());
+        assert_eq!(left.max(), expected.max());
+
+        // The global extremes also match when merging in the other direction.
+        let mut left = MinMaxAggregator::new(ForwardDecay::new(landmark, g));
+        let mut right = MinMaxAggregator::new(ForwardDecay::new(later, g));
+
+        stream[..3].iter().for_each(|item| left.update(*item));
+        stream[3..].iter().for_each(|item| right.update(*item));
+        right.merge(left).unwrap();
+
+        assert_eq!(right.min(), expected.min());
+        assert_eq!(right.max(), expected.max());
+
+        let mismatched = MinMaxAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.1)));
+
+        assert_eq!(right.merge(mismatched), Err(Error::DecayMismatch));
+    }
+}
@@ -2,22 +2,120 @@
 
 use std::time::Instant;
 
-pub use basic::BasicAggregator;
-pub use minmax::MinMaxAggregator;
+pub use accumulator::{Accumulator, Kahan, Naive};
+pub use basic::{AggregatorState, BackwardsTime, BasicAggregator, BasicAggregatorBuilder, PreLandmarkPolicy, Snapshot, Stats};
+pub use bucketed::BucketedAggregator;
+pub use centroid::CentroidAggregator;
+pub use checkpoint::{Checkpoint, CheckpointData};
+pub use context::QueryContext;
+pub use dedup::DedupAggregator;
+pub use hierarchical::HierarchicalQuantile;
+pub use minmax::{MinMaxAggregator, MinMaxState};
+pub use mode::ApproxModeAggregator;
+pub use multivariate::{MultivariateAggregator, MultivariateState};
+pub use partitioned::PartitionedAggregator;
+pub use quantile::{QuantileAggregator, QuantileState};
+pub use ring::RingAggregator;
 pub use sign::SignAggregator;
+pub use sum::DecayedSum;
+pub use tap::TapAggregator;
+pub use tdigest::DecayedTDigest;
+pub use trending::TrendingAggregator;
+pub use variance::{VarianceAggregator, VarianceState};
 
+mod accumulator;
 mod basic;
+mod bucketed;
+mod centroid;
+mod checkpoint;
+mod context;
+mod dedup;
+mod hierarchical;
 mod minmax;
+mod mode;
+mod multivariate;
+mod partitioned;
+mod quantile;
+mod ring;
 mod sign;
+mod sum;
+mod tap;
+mod tdigest;
+mod trending;
+mod variance;
 
 /// Aggregates information about items in an unordered stream.
 pub trait Aggregator {
     type Item;
 
+    /// The result of a query, i.e. the decayed sum for a [BasicAggregator].
+    type Output;
+
     /// Update the aggregation with the given item.
     fn update(&mut self, item: Self::Item);
 
     /// Reset the aggregation to the initial state.
     /// This is equivalent to creating a new aggregator with the same decay model and the given landmark.
     fn reset(&mut self, landmark: Instant);
-}
\ No newline at end of file
+
+    /// The primary result of the aggregation at the given time,
+    /// for generic code that does not know the concrete aggregator.
+    /// Each aggregator also offers more specific queries of its own.
+    fn query(&self, timestamp: Instant) -> Self::Output;
+
+    /// The primary result of the aggregation at the given time, or [None] when it is not finite,
+    /// for aggregators whose result is a single number.
+    /// Normalized results divide by g(t − L), which is 0 for polynomial decay at the landmark
+    /// and overflows to infinity for exponential decay far from the landmark,
+    /// and most results are NaN when no items have been aggregated.
+    fn query_finite(&self, timestamp: Instant) -> Option<f64>
+    where
+        Self: Aggregator<Output = f64> + Sized,
+    {
+        Some(self.query(timestamp)).filter(|result| result.is_finite())
+    }
+}
+
+/// Whether a query returns static or decayed values, for aggregators that offer both.
+///
+/// Static values are the sums of static weights g(ti − L), before normalization.
+/// They are relative to the landmark, so they are only comparable between aggregators, or over time,
+/// while the landmark stays fixed; moving the landmark rescales them.
+/// Decayed values are normalized by g(t − L) at the query time, so they are comparable regardless of the landmark.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Normalization {
+    /// The landmark-relative value, ignoring the query time.
+    Static,
+    /// The value decayed to the query time.
+    Decayed,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::{ForwardDecay, g};
+    use super::*;
+
+    #[test]
+    fn dyn_query() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregators: Vec<Box<dyn Aggregator<Item = (Instant, f64), Output = f64>>> = vec![
+            Box::new(BasicAggregator::new(decay)),
+            Box::new(RingAggregator::new(decay, 2)),
+            Box::new(BucketedAggregator::new(decay, Duration::from_secs(1))),
+            Box::new(QuantileAggregator::new(decay)),
+        ];
+
+        for aggregator in aggregators.iter_mut() {
+            for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+                aggregator.update((landmark + Duration::from_secs(offset), value));
+            }
+        }
+
+        let results: Vec<f64> = aggregators.iter().map(|aggregator| aggregator.query(now)).collect();
+
+        assert_eq!(results, vec![9.67, 9.67, 9.67, 6.0]);
+    }
+}
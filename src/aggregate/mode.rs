@@ -0,0 +1,126 @@
+use std::hash::Hash;
+use std::time::Instant;
+use crate::ForwardDecay;
+use crate::aggregate::Aggregator;
+use crate::g::Function;
+use crate::space_saving::BTreeSpaceSaving;
+
+/// An aggregation computation over a stream of keys to determine the approximate decayed mode,
+/// using bounded memory regardless of the number of distinct keys.
+///
+/// The keys are tracked by a [BTreeSpaceSaving] summary with the given capacity.
+/// When the summary is full, the key with the smallest decayed count is evicted in favor of a new key,
+/// which inherits the evicted count as its error.
+/// Any key whose decayed frequency exceeds 1/capacity of the total decayed hits is guaranteed to be retained,
+/// and the decayed count of the reported mode overestimates the true count by at most that same amount.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{ApproxModeAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Exponential::new(0.01));
+/// let landmark = decay.landmark();
+/// let stream = vec![
+///     ("a", landmark + Duration::from_secs(5)),
+///     ("b", landmark + Duration::from_secs(7)),
+///     ("a", landmark + Duration::from_secs(3)),
+///     ("c", landmark + Duration::from_secs(8)),
+///     ("a", landmark + Duration::from_secs(4)),
+/// ];
+///
+/// let mut aggregator = ApproxModeAggregator::new(2, decay);
+///
+/// for item in stream {
+///     aggregator.update(item);
+/// }
+///
+/// assert_eq!(aggregator.mode(), Some(&"a"));
+///
+/// aggregator.reset(landmark);
+///
+/// assert_eq!(aggregator.mode(), None);
+/// ```
+pub struct ApproxModeAggregator<K, G> where K: Ord {
+    summary: BTreeSpaceSaving<K, G>,
+}
+
+impl<K, G> Aggregator for ApproxModeAggregator<K, G>
+where
+    K: Clone + Hash + Eq + Ord,
+    G: Function + Clone,
+{
+    type Item = (K, Instant);
+    /// The retained key with the largest decayed count, if any.
+    type Output = Option<K>;
+
+    fn update(&mut self, (key, timestamp): (K, Instant)) {
+        self.summary.hit_at(key, timestamp);
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        let mut decay = self.summary.decay().clone();
+
+        decay.set_landmark(landmark);
+
+        self.summary = BTreeSpaceSaving::new(self.summary.capacity(), decay);
+    }
+
+    fn query(&self, _: Instant) -> Option<K> {
+        self.mode().cloned()
+    }
+}
+
+impl<K, G> ApproxModeAggregator<K, G>
+where
+    K: Clone + Hash + Eq + Ord,
+    G: Function,
+{
+    /// Initializes a new aggregator that tracks at most capacity keys at once.
+    pub fn new(capacity: usize, decay: ForwardDecay<G>) -> Self {
+        Self {
+            summary: BTreeSpaceSaving::new(capacity, decay),
+        }
+    }
+
+    /// The retained key with the largest decayed count, if any.
+    #[must_use]
+    pub fn mode(&self) -> Option<&K> {
+        match self.summary.top(1) {
+            Ok(top) | Err(top) => top.first().copied(),
+        }
+    }
+
+    /// The underlying summary of retained keys.
+    pub fn summary(&self) -> &BTreeSpaceSaving<K, G> {
+        &self.summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn high_cardinality() {
+        let landmark = Instant::now();
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(0.001));
+        let mut aggregator = ApproxModeAggregator::new(16, decay);
+
+        for i in 0..10_000u32 {
+            let timestamp = landmark + Duration::from_millis(i as u64);
+
+            if i % 4 == 0 {
+                aggregator.update((u32::MAX, timestamp));
+            } else {
+                aggregator.update((i, timestamp));
+            }
+        }
+
+        assert_eq!(aggregator.mode(), Some(&u32::MAX));
+        assert_eq!(aggregator.summary().capacity(), 16);
+    }
+}
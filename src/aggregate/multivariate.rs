@@ -0,0 +1,277 @@
+use std::time::Instant;
+use crate::{Error, ForwardDecay};
+use crate::aggregate::{Aggregator, Checkpoint, CheckpointData};
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// The raw static accumulators of a [MultivariateAggregator], relative to the landmark.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MultivariateState<const N: usize> {
+    pub count: f64,
+    pub sums: [f64; N],
+    pub products: [[f64; N]; N],
+    pub landmark: Instant,
+}
+
+/// Decayed first and second moments over a stream of N-dimensional vectors,
+/// used to compute the decayed mean vector and covariance matrix.
+///
+/// ## Example
+/// ```rust
+/// use std::time::Instant;
+/// use fermentation::ForwardDecay;
+/// use fermentation::aggregate::{MultivariateAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), ());
+/// let landmark = decay.landmark();
+/// let mut aggregator = MultivariateAggregator::new(decay);
+///
+/// aggregator.update((landmark, [1.0, 2.0]));
+/// aggregator.update((landmark, [3.0, 6.0]));
+///
+/// assert_eq!(aggregator.mean(landmark), [2.0, 4.0]);
+/// assert_eq!(aggregator.covariance(landmark), [[1.0, 2.0], [2.0, 4.0]]);
+/// ```
+#[derive(Copy, Clone)]
+pub struct MultivariateAggregator<G, const N: usize> {
+    decay: ForwardDecay<G>,
+    count: f64,
+    sums: [f64; N],
+    products: [[f64; N]; N],
+}
+
+impl<G, const N: usize> Aggregator for MultivariateAggregator<G, N> where G: Function {
+    type Item = (Instant, [f64; N]);
+    /// The decayed mean of each dimension.
+    type Output = [f64; N];
+
+    fn update(&mut self, (timestamp, values): (Instant, [f64; N])) {
+        let static_weight = self.decay.static_weight(timestamp);
+
+        self.count += static_weight;
+
+        for i in 0..N {
+            self.sums[i] += static_weight * values[i];
+
+            for j in 0..N {
+                self.products[i][j] += static_weight * values[i] * values[j];
+            }
+        }
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+        self.count = 0.0;
+        self.sums = [0.0; N];
+        self.products = [[0.0; N]; N];
+    }
+
+    fn query(&self, timestamp: Instant) -> [f64; N] {
+        self.mean(timestamp)
+    }
+}
+
+impl<const N: usize> MultivariateAggregator<Exponential, N> {
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        self.count /= factor;
+
+        for i in 0..N {
+            self.sums[i] /= factor;
+
+            for j in 0..N {
+                self.products[i][j] /= factor;
+            }
+        }
+    }
+}
+
+impl<const N: usize> SyncLandmark for MultivariateAggregator<Exponential, N> {
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G, const N: usize> MultivariateAggregator<G, N>
+where
+    G: Function,
+{
+    pub fn new(decay: ForwardDecay<G>) -> Self {
+        Self {
+            decay,
+            count: 0.0,
+            sums: [0.0; N],
+            products: [[0.0; N]; N],
+        }
+    }
+
+    /// The decayed count of vectors.
+    #[must_use]
+    pub fn count(&self, timestamp: Instant) -> f64 {
+        self.count / self.decay.normalizing_factor(timestamp)
+    }
+
+    /// The decayed weighted mean of each dimension.
+    /// The normalizing factor cancels out of the ratio, so the mean does not depend on the query time.
+    #[must_use]
+    pub fn mean(&self, _: Instant) -> [f64; N] {
+        self.sums.map(|sum| sum / self.count)
+    }
+
+    /// The decayed weighted (population) covariance matrix.
+    /// Like the mean, it does not depend on the query time.
+    #[must_use]
+    pub fn covariance(&self, timestamp: Instant) -> [[f64; N]; N] {
+        let mean = self.mean(timestamp);
+        let mut covariance = [[0.0; N]; N];
+
+        for i in 0..N {
+            for j in 0..N {
+                covariance[i][j] = self.products[i][j] / self.count - mean[i] * mean[j];
+            }
+        }
+
+        covariance
+    }
+
+    /// The raw static accumulators of this aggregator, before normalization.
+    pub fn static_state(&self) -> MultivariateState<N> {
+        MultivariateState {
+            count: self.count,
+            sums: self.sums,
+            products: self.products,
+            landmark: self.decay.landmark(),
+        }
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+}
+
+impl<G, const N: usize> Checkpoint<G> for MultivariateAggregator<G, N>
+where
+    G: Function,
+{
+    /// Captures the static count, the N sums, then the N × N products in row-major order.
+    fn checkpoint(&self, reference: Instant) -> CheckpointData {
+        let values = std::iter::once(self.count)
+            .chain(self.sums)
+            .chain(self.products.into_iter().flatten())
+            .collect();
+
+        CheckpointData::new(self.decay.landmark(), reference, values)
+    }
+
+    fn restore(data: &CheckpointData, mut decay: ForwardDecay<G>, reference: Instant) -> Result<Self, Error> {
+        if data.values.len() != 1 + N + N * N {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        decay.set_landmark(data.landmark(reference)?);
+
+        let mut aggregator = Self::new(decay);
+        let (sums, products) = data.values[1..].split_at(N);
+
+        aggregator.count = data.values[0];
+        aggregator.sums.copy_from_slice(sums);
+
+        for (row, values) in aggregator.products.iter_mut().zip(products.chunks_exact(N)) {
+            row.copy_from_slice(values);
+        }
+
+        Ok(aggregator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn correlated() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, ());
+        let mut aggregator = MultivariateAggregator::new(fd);
+
+        for (x, noise) in [(1.0, 0.1), (2.0, -0.1), (3.0, 0.1), (4.0, -0.1), (5.0, 0.0)] {
+            aggregator.update((landmark + Duration::from_secs(x as u64), [x, 2.0 * x + noise]));
+        }
+
+        let covariance = aggregator.covariance(now);
+        let epsilon = 0.05;
+
+        assert!((covariance[0][0] - 2.0).abs() < epsilon);
+        assert!((covariance[0][1] - 4.0).abs() < epsilon);
+        assert_eq!(covariance[0][1], covariance[1][0]);
+        assert!((covariance[1][1] - 8.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn static_state() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = MultivariateAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for x in [5.0, 7.0, 3.0] {
+            aggregator.update((landmark + Duration::from_secs(x as u64), [x, x * x]));
+        }
+
+        let state = aggregator.static_state();
+        let factor = aggregator.decay().normalizing_factor(now);
+
+        assert_eq!(state.landmark, landmark);
+        assert_eq!(state.count / factor, aggregator.count(now));
+        assert_eq!(state.sums.map(|sum| sum / state.count), aggregator.mean(now));
+    }
+
+    #[test]
+    fn update_landmark() {
+        let landmark = Instant::now();
+        let new_landmark = landmark + Duration::from_secs(2);
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = MultivariateAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.2)));
+        let mut clone = aggregator;
+
+        clone.reset(new_landmark);
+
+        for x in [5.0, 7.0, 3.0, 8.0] {
+            let item = (landmark + Duration::from_secs(x as u64), [x, -x]);
+
+            aggregator.update(item);
+            clone.update(item);
+        }
+
+        aggregator.update_landmark(new_landmark);
+
+        let epsilon = 0.0001;
+
+        assert!((aggregator.count(now) - clone.count(now)).abs() < epsilon);
+        assert!((aggregator.covariance(now)[0][1] - clone.covariance(now)[0][1]).abs() < epsilon);
+    }
+
+    #[test]
+    fn at_landmark() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = MultivariateAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        for x in [1.0, 2.0, 4.0] {
+            aggregator.update((landmark + Duration::from_secs(x as u64), [x, -x]));
+        }
+
+        // The normalizing factor is 0 at the landmark, but it cancels out of the mean and covariance.
+        assert_eq!(aggregator.mean(landmark), aggregator.mean(now));
+        assert_eq!(aggregator.covariance(landmark), aggregator.covariance(now));
+        assert!(aggregator.covariance(landmark).iter().flatten().all(|value| value.is_finite()));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Instant;
+use crate::{ForwardDecay, WeightedItem};
+use crate::aggregate::{Aggregator, BasicAggregator};
+use crate::g::Function;
+
+/// A [BasicAggregator] per key, i.e. per tenant, each with its own decay model.
+///
+/// A partition is created on the first item for its key, with a decay model from a user-supplied factory,
+/// so different keys may decay at different rates (e.g. premium tenants decay slower) or from different landmarks.
+///
+/// Comparing partitions requires care when their decay models differ.
+/// Decayed sums are normalized at the query time, so they are comparable as "how much recent activity",
+/// but each partition weighs the same history differently: a burst an hour ago may dominate a slowly decaying partition
+/// and be negligible in a quickly decaying one.
+/// Static sums are relative to each partition's landmark and decay function, so they are not comparable at all.
+/// Partitions with differing decay models cannot be merged with [BasicAggregator::merge].
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{Aggregator, PartitionedAggregator};
+///
+/// let landmark = Instant::now();
+/// let now = landmark + Duration::from_secs(10);
+/// let mut aggregator = PartitionedAggregator::new(|tenant: &&str| {
+///     let alpha = if *tenant == "premium" { 0.01 } else { 0.1 };
+///
+///     ForwardDecay::new(landmark, g::Exponential::new(alpha))
+/// });
+///
+/// aggregator.update(("premium", (landmark, 1.0)));
+/// aggregator.update(("basic", (landmark, 1.0)));
+///
+/// assert!(aggregator.sum(&"premium", now) > aggregator.sum(&"basic", now));
+/// assert_eq!(aggregator.len(), 2);
+/// ```
+pub struct PartitionedAggregator<K, G, I, F> {
+    partitions: HashMap<K, BasicAggregator<G, I>>,
+    factory: F,
+}
+
+impl<K, G, I, F> Aggregator for PartitionedAggregator<K, G, I, F>
+where
+    K: Eq + Hash,
+    G: Function,
+    I: WeightedItem,
+    F: Fn(&K) -> ForwardDecay<G>,
+{
+    type Item = (K, I);
+    /// The total of the decayed sums of every partition.
+    type Output = f64;
+
+    fn update(&mut self, (key, item): (K, I)) {
+        self.partition_mut(key).update(item);
+    }
+
+    /// Resets every partition to the given landmark, keeping their decay functions.
+    /// Partitions created afterwards still take their landmark from the factory.
+    fn reset(&mut self, landmark: Instant) {
+        for aggregator in self.partitions.values_mut() {
+            aggregator.reset(landmark);
+        }
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.partitions.values().map(|aggregator| aggregator.sum(timestamp)).sum()
+    }
+}
+
+impl<K, G, I, F> PartitionedAggregator<K, G, I, F>
+where
+    K: Eq + Hash,
+    G: Function,
+    I: WeightedItem,
+    F: Fn(&K) -> ForwardDecay<G>,
+{
+    /// Initializes an aggregator without partitions, creating the decay model of each new partition with the given factory.
+    pub fn new(factory: F) -> Self {
+        Self {
+            partitions: HashMap::new(),
+            factory,
+        }
+    }
+
+    /// The partition for the given key, creating it with a decay model from the factory if it does not exist yet.
+    pub fn partition_mut(&mut self, key: K) -> &mut BasicAggregator<G, I> {
+        let factory = &self.factory;
+
+        self.partitions.entry(key).or_insert_with_key(|key| BasicAggregator::new(factory(key)))
+    }
+
+    /// The partition for the given key, if it has seen any items.
+    #[must_use]
+    pub fn partition(&self, key: &K) -> Option<&BasicAggregator<G, I>> {
+        self.partitions.get(key)
+    }
+
+    /// The decayed sum of the given key's partition, or 0 when it has no partition.
+    #[must_use]
+    pub fn sum(&self, key: &K, timestamp: Instant) -> f64 {
+        self.partition(key).map_or(0.0, |aggregator| aggregator.sum(timestamp))
+    }
+
+    /// The decayed count of the given key's partition, or 0 when it has no partition.
+    #[must_use]
+    pub fn count(&self, key: &K, timestamp: Instant) -> f64 {
+        self.partition(key).map_or(0.0, |aggregator| aggregator.count(timestamp))
+    }
+
+    /// The keys and partitions, in arbitrary order.
+    pub fn partitions(&self) -> impl Iterator<Item = (&K, &BasicAggregator<G, I>)> {
+        self.partitions.iter()
+    }
+
+    /// The number of partitions.
+    pub fn len(&self) -> usize {
+        self.partitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.partitions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn per_key_decay() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let slow = g::Exponential::new(0.01);
+        let fast = g::Exponential::new(0.5);
+        let mut aggregator = PartitionedAggregator::new(|tenant: &u8| {
+            ForwardDecay::new(landmark, if *tenant == 0 { slow } else { fast })
+        });
+        let mut expected_slow = BasicAggregator::new(ForwardDecay::new(landmark, slow));
+        let mut expected_fast = BasicAggregator::new(ForwardDecay::new(landmark, fast));
+
+        for (offset, value) in [(1, 4.0), (3, 2.0), (8, 5.0)] {
+            let item = (landmark + Duration::from_secs(offset), value);
+
+            aggregator.update((0, item));
+            aggregator.update((1, item));
+            expected_slow.update(item);
+            expected_fast.update(item);
+        }
+
+        // Each partition decays at its own rate, as if aggregated on its own.
+        assert_eq!(aggregator.len(), 2);
+        assert_eq!(*aggregator.partition_mut(0).decay().g(), slow);
+        assert_eq!(*aggregator.partition_mut(1).decay().g(), fast);
+        assert_eq!(aggregator.sum(&0, now), expected_slow.sum(now));
+        assert_eq!(aggregator.sum(&1, now), expected_fast.sum(now));
+        assert_eq!(aggregator.count(&0, now), expected_slow.count(now));
+        assert_eq!(aggregator.count(&1, now), expected_fast.count(now));
+        assert!(aggregator.sum(&0, now) > aggregator.sum(&1, now));
+        assert_eq!(aggregator.query(now), expected_slow.sum(now) + expected_fast.sum(now));
+
+        // Updating one partition leaves the other unchanged.
+        aggregator.update((1, (landmark + Duration::from_secs(9), 100.0)));
+
+        assert_eq!(aggregator.sum(&0, now), expected_slow.sum(now));
+        assert_ne!(aggregator.sum(&1, now), expected_fast.sum(now));
+        assert_eq!(aggregator.sum(&2, now), 0.0);
+        assert!(aggregator.partition(&2).is_none());
+
+        aggregator.reset(now);
+
+        assert_eq!(aggregator.len(), 2);
+        assert_eq!(aggregator.query(now + Duration::from_secs(1)), 0.0);
+    }
+}
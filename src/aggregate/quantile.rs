@@ -0,0 +1,389 @@
+use std::marker::PhantomData;
+use std::time::Instant;
+use crate::{Error, ForwardDecay, Item};
+use crate::aggregate::{Aggregator, Checkpoint, CheckpointData};
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// The raw state of a [QuantileAggregator]: the retained values along with their static weights in value order,
+/// and the total static weight, relative to the landmark.
+/// Unlike the owned states of aggregators with scalar accumulators, such as [AggregatorState](crate::aggregate::AggregatorState),
+/// the state borrows the retained values, which grow with the number of items, rather than copying them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct QuantileState<'a> {
+    pub entries: &'a [(f64, f64)],
+    pub count: f64,
+    pub landmark: Instant,
+}
+
+/// Exact decayed quantiles over a stream of items.
+///
+/// Every value is retained along with its static weight, in sorted order,
+/// so memory grows linearly with the number of items until the next reset.
+/// Each update inserts into a sorted [Vec], which takes O(n) time in the number of retained values.
+/// This is an exact reference rather than a sketch; for bounded memory, see [DecayedTDigest](crate::aggregate::DecayedTDigest)
+/// or [HierarchicalQuantile](crate::aggregate::HierarchicalQuantile).
+/// The φ-quantile is the smallest value whose cumulative decayed weight is at least φ times the total decayed weight.
+/// Items with a NaN value are ignored.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{QuantileAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let stream = vec![
+///     (landmark + Duration::from_secs(5), 4.0),
+///     (landmark + Duration::from_secs(7), 8.0),
+///     (landmark + Duration::from_secs(3), 3.0),
+///     (landmark + Duration::from_secs(8), 6.0),
+///     (landmark + Duration::from_secs(4), 4.0),
+/// ];
+///
+/// let mut aggregator = QuantileAggregator::new(decay);
+///
+/// for item in stream {
+///     aggregator.update(item);
+/// }
+///
+/// // Cumulative weights in value order: 3.0 => 0.09, 4.0 => 0.50, 6.0 => 1.14, 8.0 => 1.63.
+/// assert_eq!(aggregator.quantile(0.5, now), 6.0);
+/// assert_eq!(aggregator.quantiles(&[0.0, 0.5, 1.0], now), vec![3.0, 6.0, 8.0]);
+/// ```
+#[derive(Clone)]
+pub struct QuantileAggregator<G, I> {
+    decay: ForwardDecay<G>,
+    count: f64,
+    entries: Vec<(f64, f64)>,
+    _phantom_data: PhantomData<I>
+}
+
+impl<G, I> Aggregator for QuantileAggregator<G, I> where G: Function, I: Item {
+    type Item = I;
+    /// The decayed median.
+    type Output = f64;
+
+    fn update(&mut self, item: I) {
+        let value = item.value();
+
+        if value.is_nan() {
+            return;
+        }
+
+        let static_weight = self.decay.static_weight(&item);
+        let index = self.entries.partition_point(|(v, _)| *v <= value);
+
+        self.count += static_weight;
+        self.entries.insert(index, (value, static_weight));
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+        self.count = 0.0;
+        self.entries.clear();
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.quantile(0.5, timestamp)
+    }
+}
+
+impl<I> QuantileAggregator<Exponential, I>
+where
+    I: Item,
+{
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        self.count /= factor;
+
+        for (_, weight) in self.entries.iter_mut() {
+            *weight /= factor;
+        }
+    }
+}
+
+impl<I> SyncLandmark for QuantileAggregator<Exponential, I>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G, I> QuantileAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    pub fn new(decay: ForwardDecay<G>) -> Self {
+        Self {
+            decay,
+            count: 0.0,
+            entries: Vec::new(),
+            _phantom_data: Default::default()
+        }
+    }
+
+    /// Initializes a new aggregator with room for at least the given number of values before reallocating,
+    /// which avoids repeated reallocation during warm-up when the size of the stream is known.
+    pub fn with_capacity(decay: ForwardDecay<G>, capacity: usize) -> Self {
+        let mut aggregator = Self::new(decay);
+
+        aggregator.entries.reserve_exact(capacity);
+        aggregator
+    }
+
+    /// The number of values the aggregator can retain without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// The decayed φ-quantile at the given time, or NaN when no items have been aggregated.
+    #[must_use]
+    pub fn quantile(&self, phi: f64, timestamp: Instant) -> f64 {
+        self.quantiles(&[phi], timestamp)[0]
+    }
+
+    /// The decayed φ-quantile, or [None] when it is not finite, i.e. when no items have been aggregated,
+    /// or when the weights cannot be normalized at the given time.
+    #[must_use]
+    pub fn quantile_finite(&self, phi: f64, timestamp: Instant) -> Option<f64> {
+        Some(self.quantile(phi, timestamp)).filter(|quantile| quantile.is_finite())
+    }
+
+    /// The decayed quantiles for each of the given φ values, computed in a single pass over the retained values.
+    /// The results are in the same order as the given φ values. The quantile for a NaN φ is NaN.
+    #[must_use]
+    pub fn quantiles(&self, phis: &[f64], timestamp: Instant) -> Vec<f64> {
+        let factor = self.decay.normalizing_factor(timestamp);
+        let total = self.count / factor;
+        let mut order: Vec<usize> = (0..phis.len()).collect();
+        let mut results = vec![f64::NAN; phis.len()];
+
+        order.retain(|index| !phis[*index].is_nan());
+        order.sort_by(|a, b| phis[*a].total_cmp(&phis[*b]));
+
+        let mut pending = order.into_iter().peekable();
+        let mut cumulative = 0.0;
+
+        for (value, weight) in self.entries.iter() {
+            cumulative += weight / factor;
+
+            while let Some(index) = pending.next_if(|index| cumulative >= phis[*index] * total) {
+                results[index] = *value;
+            }
+        }
+
+        // Guards against rounding leaving the highest quantiles just short of the total.
+        if let Some((last, _)) = self.entries.last() {
+            for index in pending {
+                results[index] = *last;
+            }
+        }
+
+        results
+    }
+
+    /// The decayed fraction of the total weight belonging to values strictly below the given value,
+    /// for converting a raw measurement into a score in [0, 1]. Returns NaN when no items have been aggregated.
+    #[must_use]
+    pub fn percentile_rank(&self, value: f64, timestamp: Instant) -> f64 {
+        let factor = self.decay.normalizing_factor(timestamp);
+        let end = self.entries.partition_point(|(v, _)| *v < value);
+        let below: f64 = self.entries[..end].iter().map(|(_, weight)| weight / factor).sum();
+
+        below / (self.count / factor)
+    }
+
+    /// The decayed count of the retained values.
+    #[must_use]
+    pub fn count(&self, timestamp: Instant) -> f64 {
+        self.count / self.decay.normalizing_factor(timestamp)
+    }
+
+    /// The raw state of this aggregator, with static (not normalized) weights.
+    pub fn static_state(&self) -> QuantileState<'_> {
+        QuantileState {
+            entries: &self.entries,
+            count: self.count,
+            landmark: self.decay.landmark(),
+        }
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+}
+
+impl<G, I> Checkpoint<G> for QuantileAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    /// Captures the static count, then each retained value followed by its static weight, in value order.
+    fn checkpoint(&self, reference: Instant) -> CheckpointData {
+        let values = std::iter::once(self.count)
+            .chain(self.entries.iter().flat_map(|(value, weight)| [*value, *weight]))
+            .collect();
+
+        CheckpointData::new(self.decay.landmark(), reference, values)
+    }
+
+    fn restore(data: &CheckpointData, mut decay: ForwardDecay<G>, reference: Instant) -> Result<Self, Error> {
+        let (count, entries) = data.values.split_first().ok_or(Error::InvalidCheckpoint)?;
+
+        if !entries.len().is_multiple_of(2) || entries.iter().step_by(2).any(|value| value.is_nan()) {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        decay.set_landmark(data.landmark(reference)?);
+
+        let mut aggregator = Self::new(decay);
+
+        aggregator.count = *count;
+        aggregator.entries = entries.chunks_exact(2).map(|entry| (entry[0], entry[1])).collect();
+        aggregator.entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(aggregator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn quantiles() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let fd = ForwardDecay::new(landmark, g::Exponential::new(0.01));
+        let mut aggregator = QuantileAggregator::new(fd);
+
+        let stream: Vec<(Instant, f64)> = (1..=100u64)
+            .map(|i| (landmark + Duration::from_secs(i), ((i * 37) % 101) as f64))
+            .collect();
+
+        for item in stream.iter() {
+            aggregator.update(*item);
+        }
+
+        // The smallest value whose cumulative decayed weight reaches φ of the total, from the decayed weights at query time.
+        let mut weighted: Vec<(f64, f64)> = stream.iter().map(|item| (item.1, fd.weight(*item, now))).collect();
+
+        weighted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let total: f64 = weighted.iter().map(|(_, weight)| weight).sum();
+        let brute_force = |phi: f64| {
+            let mut cumulative = 0.0;
+
+            weighted.iter()
+                .find(|(_, weight)| {
+                    cumulative += weight;
+                    cumulative >= phi * total
+                })
+                .map_or(weighted[weighted.len() - 1].0, |(value, _)| *value)
+        };
+
+        let phis = [0.99, 0.0, 0.5, 0.25, 0.9, 0.95, 1.0];
+        let batch = aggregator.quantiles(&phis, now);
+        let expected: Vec<f64> = phis.iter().map(|phi| brute_force(*phi)).collect();
+
+        assert_eq!(batch, expected);
+        assert!(batch[2] <= batch[4] && batch[4] <= batch[5] && batch[5] <= batch[0]);
+
+        // A NaN φ has a NaN quantile, without disturbing the others.
+        let with_nan = aggregator.quantiles(&[f64::NAN, 0.5, -f64::NAN], now);
+
+        assert!(with_nan[0].is_nan() && with_nan[2].is_nan());
+        assert_eq!(with_nan[1], batch[2]);
+    }
+
+    #[test]
+    fn static_state() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = QuantileAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let state = aggregator.static_state();
+        let median = state.entries.iter()
+            .scan(0.0, |cumulative, (value, weight)| {
+                *cumulative += weight;
+                Some((*value, *cumulative))
+            })
+            .find(|(_, cumulative)| *cumulative >= 0.5 * state.count)
+            .map(|(value, _)| value);
+
+        assert_eq!(state.landmark, landmark);
+        assert_eq!(state.entries.len(), 5);
+        assert_eq!(state.count / aggregator.decay().normalizing_factor(now), aggregator.count(now));
+        assert_eq!(median, Some(aggregator.quantile(0.5, now)));
+    }
+
+    #[test]
+    fn percentile_rank() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(101);
+        let mut aggregator = QuantileAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.01)));
+
+        for i in 1..=100u64 {
+            aggregator.update((landmark + Duration::from_secs(i), ((i * 37) % 101) as f64));
+        }
+
+        let center = aggregator.quantile(0.5, now);
+        let epsilon = 0.05;
+
+        assert!((aggregator.percentile_rank(center, now) - 0.5).abs() < epsilon);
+        assert!(aggregator.percentile_rank(1000.0, now) > 1.0 - epsilon);
+        assert_eq!(aggregator.percentile_rank(0.0, now), 0.0);
+    }
+
+    #[test]
+    fn empty() {
+        let landmark = Instant::now();
+        let aggregator: QuantileAggregator<_, (Instant, f64)> = QuantileAggregator::new(ForwardDecay::new(landmark, ()));
+
+        assert!(aggregator.quantile(0.5, landmark).is_nan());
+        assert!(aggregator.percentile_rank(0.5, landmark).is_nan());
+        assert_eq!(aggregator.quantile_finite(0.5, landmark), None);
+    }
+
+    #[test]
+    fn with_capacity() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(101);
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(0.01));
+        let mut preallocated = QuantileAggregator::with_capacity(decay, 100);
+        let mut control = QuantileAggregator::new(decay);
+
+        assert!(preallocated.capacity() >= 100);
+
+        let capacity = preallocated.capacity();
+
+        for i in 1..=100u64 {
+            let item = (landmark + Duration::from_secs(i), ((i * 37) % 101) as f64);
+
+            preallocated.update(item);
+            control.update(item);
+        }
+
+        assert_eq!(preallocated.capacity(), capacity);
+        assert_eq!(preallocated.quantiles(&[0.1, 0.5, 0.9], now), control.quantiles(&[0.1, 0.5, 0.9], now));
+        assert_eq!(preallocated.static_state(), control.static_state());
+    }
+}
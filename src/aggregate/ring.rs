@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+use crate::{ForwardDecay, Item, WeightedItem};
+use crate::aggregate::{Aggregator, BasicAggregator};
+use crate::g::{Exponential, Function, LandmarkWindow};
+use crate::landmark::SyncLandmark;
+
+/// A [BasicAggregator] that also retains the most recent raw items in a bounded ring buffer,
+/// for exact recomputation or inspection alongside the decayed aggregates over the full stream.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{RingAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let mut aggregator = RingAggregator::new(decay, 2);
+///
+/// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+/// aggregator.update((landmark + Duration::from_secs(7), 8.0));
+/// aggregator.update((landmark + Duration::from_secs(3), 3.0));
+///
+/// assert_eq!(aggregator.recent(), &[(landmark + Duration::from_secs(7), 8.0), (landmark + Duration::from_secs(3), 3.0)]);
+/// assert_eq!(aggregator.aggregator().sum(now), 5.19);
+/// ```
+#[derive(Clone)]
+pub struct RingAggregator<G, I> {
+    aggregator: BasicAggregator<G, I>,
+    capacity: usize,
+    recent: VecDeque<I>,
+}
+
+impl<G, I> Aggregator for RingAggregator<G, I> where G: Function, I: WeightedItem + Clone {
+    type Item = I;
+    /// The decayed sum over the full stream.
+    type Output = f64;
+
+    fn update(&mut self, item: I) {
+        if self.recent.len() == self.capacity {
+            self.recent.pop_front();
+        }
+
+        self.recent.push_back(item.clone());
+        self.aggregator.update(item);
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.aggregator.reset(landmark);
+        self.recent.clear();
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.aggregator.sum(timestamp)
+    }
+}
+
+impl<G, I> RingAggregator<G, I>
+where
+    G: Function,
+    I: WeightedItem + Clone,
+{
+    /// Merges another aggregator into this one by re-ingesting the other's retained items under this aggregator's decay.
+    /// Unlike an exact merge of the decayed aggregates, this works even when the two aggregators use different decay functions.
+    ///
+    /// The result is an approximation: only the recent items retained by the other aggregator are re-ingested,
+    /// so any older items it aggregated do not contribute to the merged aggregates.
+    /// The re-ingested items also enter this aggregator's ring buffer, possibly evicting its own recent items.
+    pub fn merge_reingest<H>(&mut self, other: &RingAggregator<H, I>) {
+        for item in other.recent.iter() {
+            self.update(item.clone());
+        }
+    }
+}
+
+impl<I> RingAggregator<Exponential, I>
+where
+    I: Item,
+{
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        self.aggregator.update_landmark(landmark);
+    }
+}
+
+impl<I> SyncLandmark for RingAggregator<Exponential, I>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.aggregator.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<I> RingAggregator<LandmarkWindow, I>
+where
+    I: WeightedItem + Clone,
+{
+    /// Moves the landmark of a landmark window, so that items at or before the new landmark drop out of the window.
+    /// Retained items outside the new window are discarded and the aggregates are rebuilt from the remaining retained items,
+    /// which restarts the diagnostic counters of the aggregator.
+    ///
+    /// The decayed aggregates cannot subtract items, so the rebuilt aggregates are only exact
+    /// when the ring buffer retains every item after the new landmark;
+    /// items that were already evicted from the ring buffer are lost.
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        self.recent.retain(|item| item.age(landmark) > 0.0);
+        self.aggregator.reset(landmark);
+
+        for item in self.recent.iter() {
+            self.aggregator.update(item.clone());
+        }
+    }
+}
+
+impl<G, I> RingAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    /// Initializes a new aggregator that retains up to the given number of recent items.
+    /// The ring buffer is allocated up front, so it never reallocates.
+    ///
+    /// ## Panic
+    /// Panics when the capacity is 0.
+    pub fn new(decay: ForwardDecay<G>, capacity: usize) -> Self {
+        if capacity == 0 {
+            panic!("capacity must be greater than 0");
+        }
+
+        Self {
+            aggregator: BasicAggregator::new(decay),
+            capacity,
+            recent: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// The most recently updated items, from oldest to newest.
+    pub fn recent(&self) -> &VecDeque<I> {
+        &self.recent
+    }
+
+    /// The decayed aggregates over every item since creation or the last reset.
+    pub fn aggregator(&self) -> &BasicAggregator<G, I> {
+        &self.aggregator
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn merge_reingest() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut polynomial = RingAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)), 4);
+        let mut exponential = RingAggregator::new(ForwardDecay::new(landmark, g::Exponential::new(0.5)), 2);
+
+        polynomial.update((landmark + Duration::from_secs(5), 4.0));
+        exponential.update((landmark + Duration::from_secs(3), 3.0));
+        exponential.update((landmark + Duration::from_secs(7), 8.0));
+        exponential.update((landmark + Duration::from_secs(8), 6.0));
+
+        polynomial.merge_reingest(&exponential);
+
+        // The oldest item of the exponential aggregator fell out of its ring buffer, so it is not merged.
+        assert_eq!(polynomial.aggregator().sum(now), 4.0 * 0.25 + 8.0 * 0.49 + 6.0 * 0.64);
+        assert_eq!(polynomial.recent().len(), 3);
+        assert_eq!(exponential.recent().len(), 2);
+    }
+
+    #[test]
+    fn recent() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(200);
+        let mut aggregator = RingAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)), 100);
+        let mut control = BasicAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        for offset in 1..=200 {
+            let item = (landmark + Duration::from_secs(offset), offset as f64);
+
+            aggregator.update(item);
+            control.update(item);
+        }
+
+        let expected: Vec<(Instant, f64)> = (101..=200)
+            .map(|offset| (landmark + Duration::from_secs(offset), offset as f64))
+            .collect();
+
+        assert_eq!(aggregator.recent().len(), 100);
+        assert_eq!(aggregator.recent(), &expected);
+        assert_eq!(aggregator.aggregator().sum(now), control.sum(now));
+        assert_eq!(aggregator.aggregator().count(now), control.count(now));
+
+        aggregator.reset(landmark);
+
+        assert!(aggregator.recent().is_empty());
+        assert_eq!(aggregator.aggregator().static_sum(), 0.0);
+    }
+
+    #[test]
+    fn landmark_window() {
+        let landmark = Instant::now();
+        let new_landmark = landmark + Duration::from_secs(5);
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = RingAggregator::new(ForwardDecay::new(landmark, g::LandmarkWindow), 10);
+
+        for offset in 1..=8 {
+            aggregator.update((landmark + Duration::from_secs(offset), offset as f64));
+        }
+
+        assert_eq!(aggregator.aggregator().sum(now), 36.0);
+
+        aggregator.update_landmark(new_landmark);
+
+        assert_eq!(aggregator.aggregator().static_state().landmark, new_landmark);
+        assert_eq!(aggregator.aggregator().sum(now), 6.0 + 7.0 + 8.0);
+        assert_eq!(aggregator.aggregator().count(now), 3.0);
+        assert_eq!(aggregator.recent().len(), 3);
+        assert!(aggregator.recent().iter().all(|item| item.age(new_landmark) > 0.0));
+    }
+}
@@ -1,7 +1,8 @@
 use std::time::Instant;
-use crate::{ForwardDecay, Item};
-use crate::aggregate::{Aggregator, BasicAggregator};
+use crate::{Error, ForwardDecay, Item, WeightedItem};
+use crate::aggregate::{Aggregator, BasicAggregator, Checkpoint, CheckpointData};
 use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
 
 /// A composite aggregator that uses a separate [BasicAggregator] for positive and negative values.
 ///
@@ -96,8 +97,10 @@ pub struct SignAggregator<G, I> {
     negative: BasicAggregator<G, I>,
 }
 
-impl<G, I> Aggregator for SignAggregator<G, I> where G: Function, I: Item {
+impl<G, I> Aggregator for SignAggregator<G, I> where G: Function, I: WeightedItem {
     type Item = I;
+    /// The decayed sums of the positive and the negative values.
+    type Output = (f64, f64);
 
     fn update(&mut self, item: I) {
         if item.value().is_sign_positive() {
@@ -111,6 +114,10 @@ impl<G, I> Aggregator for SignAggregator<G, I> where G: Function, I: Item {
         self.positive.reset(landmark);
         self.negative.reset(landmark);
     }
+
+    fn query(&self, timestamp: Instant) -> (f64, f64) {
+        (self.positive.sum(timestamp), self.negative.sum(timestamp))
+    }
 }
 
 impl<I> SignAggregator<Exponential, I>
@@ -123,6 +130,19 @@ where
     }
 }
 
+impl<I> SyncLandmark for SignAggregator<Exponential, I>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.positive.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
 impl<G, I> From<ForwardDecay<G>> for SignAggregator<G, I>
 where
     G: Function + Clone,
@@ -153,13 +173,70 @@ where
     }
 }
 
+impl<G, I> Checkpoint<G> for SignAggregator<G, I>
+where
+    G: Function + Clone,
+    I: Item,
+{
+    /// Captures the static sums and counts of the positive then the negative values,
+    /// followed by the age of the negative aggregator's landmark, which may differ from the positive one's.
+    fn checkpoint(&self, reference: Instant) -> CheckpointData {
+        let positive = self.positive.checkpoint(reference);
+        let negative = self.negative.checkpoint(reference);
+        let mut values = positive.values;
+
+        values.extend(negative.values);
+        values.push(negative.landmark_age);
+
+        CheckpointData { values, ..positive }
+    }
+
+    fn restore(data: &CheckpointData, decay: ForwardDecay<G>, reference: Instant) -> Result<Self, Error> {
+        let [positive_sum, positive_count, negative_sum, negative_count, negative_landmark_age] = data.values()?;
+        let positive = CheckpointData { landmark_age: data.landmark_age, values: vec![positive_sum, positive_count] };
+        let negative = CheckpointData { landmark_age: negative_landmark_age, values: vec![negative_sum, negative_count] };
+
+        Ok(Self {
+            positive: BasicAggregator::restore(&positive, decay.clone(), reference)?,
+            negative: BasicAggregator::restore(&negative, decay, reference)?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Add;
     use std::time::{Duration, Instant};
     use crate::g;
+    use crate::testing::{self, Query, Rescalable};
     use super::*;
 
+    impl Rescalable for SignAggregator<Exponential, (Instant, f64)> {
+        fn rescale(&mut self, landmark: Instant) {
+            self.update_landmark(landmark);
+        }
+    }
+
+    impl Query for SignAggregator<Exponential, (Instant, f64)> {
+        fn ingest(&mut self, timestamp: Instant, value: f64) {
+            self.update((timestamp, value));
+        }
+
+        fn query(&self, timestamp: Instant) -> Vec<f64> {
+            vec![
+                self.positive().sum(timestamp),
+                self.positive().count(timestamp),
+                self.negative().sum(timestamp),
+                self.negative().count(timestamp),
+            ]
+        }
+    }
+
+    #[test]
+    fn rescale_invariant() {
+        testing::assert_rescale_invariant(SignAggregator::from);
+    }
+
     #[test]
     fn example() {
         let landmark = Instant::now();
@@ -188,4 +265,29 @@ mod tests {
         assert!(aggregator.positive().average() >= (6.53 - epsilon) && aggregator.positive().average() <= (6.53 + epsilon));
         assert!(aggregator.negative().average() >= (-5.44 - epsilon) && aggregator.negative().average() <= (-5.44 + epsilon));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn checkpoint() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = SignAggregator::new(
+            ForwardDecay::new(landmark, g::Polynomial::new(2)),
+            ForwardDecay::new(landmark + Duration::from_secs(1), g::Polynomial::new(2)),
+        );
+
+        for (offset, value) in [(5, 4.0), (7, -8.0), (3, 3.0), (8, -6.0), (4, 4.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let data = aggregator.checkpoint(now);
+        let reference = now + Duration::from_secs(60);
+        let restored: SignAggregator<_, (Instant, f64)> = SignAggregator::restore(&data, ForwardDecay::new(reference, g::Polynomial::new(2)), reference).unwrap();
+        let epsilon = 1e-9;
+
+        assert_eq!(restored.positive().static_state().landmark, landmark + Duration::from_secs(60));
+        assert_eq!(restored.negative().static_state().landmark, landmark + Duration::from_secs(61));
+        assert!((restored.positive().sum(reference) - aggregator.positive().sum(now)).abs() < epsilon);
+        assert!((restored.negative().sum(reference) - aggregator.negative().sum(now)).abs() < epsilon);
+        assert!((restored.negative().count(reference) - aggregator.negative().count(now)).abs() < epsilon);
+    }
+}
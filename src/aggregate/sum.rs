@@ -0,0 +1,202 @@
+use std::time::Instant;
+use crate::{ForwardDecay, Item};
+use crate::aggregate::Normalization;
+use crate::g::Function;
+
+/// A decayed sum and count collected from an iterator, as a drop-in for standard summation patterns.
+///
+/// [FromIterator] has no way to pass a decay model, so items are collected in pairs with a borrowed [ForwardDecay].
+/// Alternatively, an empty sum can be created from a decay with [DecayedSum::new] and filled with [Extend].
+/// Unlike a [BasicAggregator](crate::aggregate::BasicAggregator), NaN values are not skipped.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::DecayedSum;
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let stream = [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)]
+///     .map(|(offset, value)| (landmark + Duration::from_secs(offset), value));
+///
+/// let sum: DecayedSum<_> = stream.iter().map(|item| (&decay, item)).collect();
+///
+/// assert_eq!(sum.sum(now), 9.67);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct DecayedSum<'a, G> {
+    decay: Option<&'a ForwardDecay<G>>,
+    sum: f64,
+    count: f64,
+}
+
+impl<'a, G> DecayedSum<'a, G>
+where
+    G: Function,
+{
+    /// An empty sum using the given decay model.
+    pub fn new(decay: &'a ForwardDecay<G>) -> Self {
+        Self {
+            decay: Some(decay),
+            sum: 0.0,
+            count: 0.0,
+        }
+    }
+
+    /// The decayed sum at the given time. An empty sum is 0 even when it has no decay model.
+    #[must_use]
+    pub fn sum(&self, timestamp: Instant) -> f64 {
+        self.decay.map_or(0.0, |decay| self.sum / decay.normalizing_factor(timestamp))
+    }
+
+    /// The decayed count at the given time. An empty sum has a count of 0 even when it has no decay model.
+    #[must_use]
+    pub fn count(&self, timestamp: Instant) -> f64 {
+        self.decay.map_or(0.0, |decay| self.count / decay.normalizing_factor(timestamp))
+    }
+
+    /// The sum of static weighted values, before normalization. Only comparable at a fixed landmark, see [Normalization].
+    #[must_use]
+    pub fn static_sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// The sum of static weights, before normalization. Only comparable at a fixed landmark, see [Normalization].
+    #[must_use]
+    pub fn static_count(&self) -> f64 {
+        self.count
+    }
+
+    /// The static or decayed sum, as chosen explicitly by the caller. The query time is ignored for static values.
+    #[must_use]
+    pub fn sum_as(&self, timestamp: Instant, normalization: Normalization) -> f64 {
+        match normalization {
+            Normalization::Static => self.static_sum(),
+            Normalization::Decayed => self.sum(timestamp),
+        }
+    }
+
+    /// The static or decayed count, as chosen explicitly by the caller. The query time is ignored for static values.
+    #[must_use]
+    pub fn count_as(&self, timestamp: Instant, normalization: Normalization) -> f64 {
+        match normalization {
+            Normalization::Static => self.static_count(),
+            Normalization::Decayed => self.count(timestamp),
+        }
+    }
+
+    /// The decay model, or [None] for a sum collected from an empty iterator.
+    pub fn decay(&self) -> Option<&'a ForwardDecay<G>> {
+        self.decay
+    }
+
+    fn add<I>(&mut self, decay: &ForwardDecay<G>, item: I)
+    where
+        I: Item,
+    {
+        let static_weight = decay.static_weight(&item);
+
+        self.sum += static_weight * item.value();
+        self.count += static_weight;
+    }
+}
+
+impl<'a, G, I> FromIterator<(&'a ForwardDecay<G>, I)> for DecayedSum<'a, G>
+where
+    G: Function,
+    I: Item,
+{
+    /// Collects items paired with the decay model to weigh them with.
+    /// Every item is expected to be paired with the same decay model; the first one is kept for queries.
+    fn from_iter<T: IntoIterator<Item = (&'a ForwardDecay<G>, I)>>(iter: T) -> Self {
+        let mut sum = Self { decay: None, sum: 0.0, count: 0.0 };
+
+        for (decay, item) in iter {
+            sum.decay.get_or_insert(decay);
+            sum.add(decay, item);
+        }
+
+        sum
+    }
+}
+
+impl<G, I> Extend<I> for DecayedSum<'_, G>
+where
+    G: Function,
+    I: Item,
+{
+    /// Adds items weighed with the decay model of this sum.
+    ///
+    /// ## Panic
+    /// Panics when adding items to a sum collected from an empty iterator, which has no decay model.
+    fn extend<T: IntoIterator<Item = I>>(&mut self, iter: T) {
+        for item in iter {
+            let decay = self.decay.expect("a sum collected from an empty iterator has no decay model");
+
+            self.add(decay, item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use crate::aggregate::{Aggregator, BasicAggregator};
+    use super::*;
+
+    #[test]
+    fn matches_basic() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let stream = [(5, 4.0), (7, 8.0), (3, 3.0), (8, 6.0), (4, 4.0)]
+            .map(|(offset, value)| (landmark + Duration::from_secs(offset), value));
+        let mut aggregator = BasicAggregator::new(decay);
+
+        for item in stream {
+            aggregator.update(item);
+        }
+
+        let collected: DecayedSum<_> = stream.iter().map(|item| (&decay, item)).collect();
+        let mut extended = DecayedSum::new(&decay);
+
+        extended.extend(stream);
+
+        assert_eq!(collected.static_sum(), aggregator.static_sum());
+        assert_eq!(collected.static_count(), aggregator.static_count());
+        assert_eq!(collected.sum(now), aggregator.sum(now));
+        assert_eq!(extended.static_sum(), aggregator.static_sum());
+        assert_eq!(extended.count(now), aggregator.count(now));
+    }
+
+    #[test]
+    fn empty() {
+        let now = Instant::now();
+        let sum: DecayedSum<()> = std::iter::empty::<(&ForwardDecay<()>, Instant)>().collect();
+
+        assert!(sum.decay().is_none());
+        assert_eq!(sum.sum(now), 0.0);
+        assert_eq!(sum.count(now), 0.0);
+    }
+
+    #[test]
+    fn normalization() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Polynomial::new(1));
+        let sum: DecayedSum<_> = [(landmark + Duration::from_secs(4), 3.0), (landmark + Duration::from_secs(6), 2.0)]
+            .into_iter()
+            .map(|item| (&decay, item))
+            .collect();
+
+        assert_eq!(sum.sum_as(now, Normalization::Static), 24.0);
+        assert_eq!(sum.count_as(now, Normalization::Static), 10.0);
+        assert_eq!(sum.sum_as(now, Normalization::Decayed), 2.4);
+        assert_eq!(sum.count_as(now, Normalization::Decayed), 1.0);
+        assert_eq!(sum.sum_as(now + Duration::from_secs(10), Normalization::Static), 24.0);
+        assert_eq!(sum.count_as(now + Duration::from_secs(10), Normalization::Decayed), 0.5);
+    }
+}
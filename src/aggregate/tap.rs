@@ -0,0 +1,76 @@
+use std::marker::PhantomData;
+use std::time::Instant;
+use crate::aggregate::Aggregator;
+
+/// An aggregator that passes every item to a user callback and does nothing else.
+/// Useful for side effects, such as logging or exporting metrics, on a stream of items.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::aggregate::{TapAggregator, Aggregator};
+///
+/// let landmark = Instant::now();
+/// let mut values = Vec::new();
+/// let mut aggregator = TapAggregator::new(|item: &(Instant, f64)| values.push(item.1));
+///
+/// aggregator.update((landmark + Duration::from_secs(5), 4.0));
+/// aggregator.update((landmark + Duration::from_secs(7), 8.0));
+/// aggregator.reset(landmark);
+///
+/// assert_eq!(values, vec![4.0, 8.0]);
+/// ```
+pub struct TapAggregator<F, I> {
+    callback: F,
+    _phantom_data: PhantomData<I>
+}
+
+impl<F, I> Aggregator for TapAggregator<F, I> where F: FnMut(&I) {
+    type Item = I;
+    type Output = ();
+
+    fn update(&mut self, item: I) {
+        (self.callback)(&item);
+    }
+
+    fn reset(&mut self, _: Instant) {}
+
+    fn query(&self, _: Instant) {}
+}
+
+impl<F, I> TapAggregator<F, I>
+where
+    F: FnMut(&I),
+{
+    pub fn new(callback: F) -> Self {
+        Self {
+            callback,
+            _phantom_data: Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::*;
+
+    #[test]
+    fn observes_every_item() {
+        let landmark = Instant::now();
+        let stream = vec![
+            (landmark + Duration::from_secs(5), 4.0),
+            (landmark + Duration::from_secs(7), 8.0),
+            (landmark + Duration::from_secs(3), 3.0),
+        ];
+
+        let mut observed = Vec::new();
+        let mut aggregator = TapAggregator::new(|item: &(Instant, f64)| observed.push(*item));
+
+        for item in stream.iter() {
+            aggregator.update(*item);
+        }
+
+        assert_eq!(observed, stream);
+    }
+}
@@ -0,0 +1,378 @@
+use std::mem;
+use std::time::Instant;
+use crate::{Error, ForwardDecay};
+use crate::aggregate::Aggregator;
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// Approximate decayed quantiles over a stream of values with bounded memory,
+/// using a merging [t-digest](https://arxiv.org/abs/1902.04023) whose centroids carry static weights.
+///
+/// Values are buffered and periodically merged into centroids, each a weighted mean of neighbouring values.
+/// A centroid spanning the quantiles q0 to q1 may span at most 1 unit of the scale function k(q) = δ / 2π × asin(2q − 1)
+/// for a compression δ, so the weight a centroid may hold shrinks towards the extremes.
+/// The tails are thus summarized at a finer resolution than the middle,
+/// and the digest retains on the order of δ centroids regardless of the length of the stream.
+///
+/// ## Decay
+/// Under forward decay, the ratio of the weights of any two items is fixed once both have arrived:
+/// every weight g(ti − L) / g(t − L) shares the normalizing factor, so time passing scales every weight equally.
+/// A centroid can therefore store the sum of the static weights of its values, and merging values into a centroid
+/// commutes with decay: the merged centroid decays exactly as its values would have individually.
+/// The digest's accuracy is the usual t-digest accuracy over the decayed distribution,
+/// although values with negligible weight, i.e. long before the latest items, may end up merged into centroids
+/// dominated by recent values. Unlike backward decay, which would require re-weighting every centroid on every query,
+/// the quantiles do not depend on the query time.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::DecayedTDigest;
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Exponential::new(0.01));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(1000);
+/// let mut digest = DecayedTDigest::new(decay, 100.0);
+///
+/// for i in 0..1000 {
+///     digest.add(landmark + Duration::from_secs(i), i as f64);
+/// }
+///
+/// // Recent values weigh more, so the decayed median is well above the undecayed median of 500.
+/// assert!(digest.quantile(0.5, now) > 900.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct DecayedTDigest<G> {
+    decay: ForwardDecay<G>,
+    compression: f64,
+    centroids: Vec<(f64, f64)>,
+    buffer: Vec<(f64, f64)>,
+    total: f64,
+    min: f64,
+    max: f64,
+}
+
+impl<G> Aggregator for DecayedTDigest<G> where G: Function {
+    type Item = (Instant, f64);
+    /// The estimated decayed median.
+    type Output = f64;
+
+    fn update(&mut self, (timestamp, value): (Instant, f64)) {
+        self.add(timestamp, value);
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+        self.centroids.clear();
+        self.buffer.clear();
+        self.total = 0.0;
+        self.min = f64::INFINITY;
+        self.max = f64::NEG_INFINITY;
+    }
+
+    fn query(&self, timestamp: Instant) -> f64 {
+        self.quantile(0.5, timestamp)
+    }
+}
+
+impl DecayedTDigest<Exponential> {
+    /// Moves the landmark, rescaling the static weight of every centroid and buffered value.
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        for (_, weight) in self.centroids.iter_mut().chain(self.buffer.iter_mut()) {
+            *weight /= factor;
+        }
+
+        self.total /= factor;
+    }
+}
+
+impl SyncLandmark for DecayedTDigest<Exponential> {
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G> DecayedTDigest<G>
+where
+    G: Function,
+{
+    /// Initializes an empty digest with the given compression δ.
+    /// Higher compression retains more centroids, for more accurate quantiles at the cost of memory.
+    ///
+    /// ## Panic
+    /// Panics when the compression is less than 1.
+    pub fn new(decay: ForwardDecay<G>, compression: f64) -> Self {
+        if compression.is_nan() || compression < 1.0 {
+            panic!("compression must be at least 1, given {compression}");
+        }
+
+        Self {
+            decay,
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            total: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Adds a value that arrived at the given time.
+    /// NaN values and values with a static weight of 0, i.e. at the landmark of a landmark window, are ignored.
+    pub fn add(&mut self, timestamp: Instant, value: f64) {
+        let static_weight = self.decay.static_weight(timestamp);
+
+        if value.is_nan() || static_weight == 0.0 {
+            return;
+        }
+
+        self.buffer.push((value, static_weight));
+        self.total += static_weight;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+
+        if self.buffer.len() as f64 >= 5.0 * self.compression {
+            self.compress();
+        }
+    }
+
+    /// Merges the buffered values into the centroids. Called automatically as the buffer fills up.
+    pub fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let mut values = mem::take(&mut self.buffer);
+
+        values.append(&mut self.centroids);
+        self.centroids = self.merge_sorted(values);
+    }
+
+    /// Combines the centroids of another digest into this one, as if this digest had seen the other's values as well.
+    /// Returns an error, without changing this digest, when the landmarks, decay functions or time scales differ.
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error>
+    where
+        G: PartialEq,
+    {
+        if self.decay.landmark() != other.decay.landmark() {
+            return Err(Error::LandmarkMismatch);
+        }
+
+        if self.decay.g() != other.decay.g() || self.decay.time_scale() != other.decay.time_scale() {
+            return Err(Error::DecayMismatch);
+        }
+
+        self.buffer.extend(other.centroids.iter().chain(other.buffer.iter()));
+        self.total += other.total;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.compress();
+
+        Ok(())
+    }
+
+    /// The estimated decayed φ-quantile, or [None] when it is not finite, i.e. when no values have been added.
+    #[must_use]
+    pub fn quantile_finite(&self, phi: f64, timestamp: Instant) -> Option<f64> {
+        Some(self.quantile(phi, timestamp)).filter(|quantile| quantile.is_finite())
+    }
+
+    /// The estimated decayed φ-quantile at the given time, interpolated between the means of neighbouring centroids,
+    /// or NaN when no values have been added.
+    #[must_use]
+    pub fn quantile(&self, phi: f64, timestamp: Instant) -> f64 {
+        let centroids = if self.buffer.is_empty() {
+            self.centroids.clone()
+        } else {
+            self.merge_sorted(self.centroids.iter().chain(self.buffer.iter()).copied().collect())
+        };
+        let factor = self.decay.normalizing_factor(timestamp);
+        let target = phi.clamp(0.0, 1.0) * self.total / factor;
+
+        let Some((&(first, first_weight), rest)) = centroids.split_first() else {
+            return f64::NAN;
+        };
+
+        // Each centroid's weight is centered on its mean, with the extremes at either end of the distribution.
+        let mut previous = (first, first_weight / factor / 2.0);
+
+        if target < previous.1 {
+            return self.min + (first - self.min) * target / previous.1;
+        }
+
+        let mut cumulative = first_weight / factor;
+
+        for (mean, weight) in rest {
+            let weight = weight / factor;
+            let center = cumulative + weight / 2.0;
+
+            if target < center {
+                return previous.0 + (mean - previous.0) * (target - previous.1) / (center - previous.1);
+            }
+
+            previous = (*mean, center);
+            cumulative += weight;
+        }
+
+        let remaining = cumulative - previous.1;
+
+        if remaining > 0.0 {
+            previous.0 + (self.max - previous.0) * ((target - previous.1) / remaining).min(1.0)
+        } else {
+            self.max
+        }
+    }
+
+    /// The decayed count of the values added to the digest.
+    #[must_use]
+    pub fn count(&self, timestamp: Instant) -> f64 {
+        self.total / self.decay.normalizing_factor(timestamp)
+    }
+
+    /// The number of centroids, excluding buffered values that have yet to be merged.
+    pub fn centroids(&self) -> usize {
+        self.centroids.len()
+    }
+
+    pub fn compression(&self) -> f64 {
+        self.compression
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+
+    /// Merges neighbouring (mean, static weight) pairs greedily in order of their means, within the size bound.
+    fn merge_sorted(&self, mut values: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+        let total: f64 = values.iter().map(|(_, weight)| weight).sum();
+        let scale = |q: f64| self.compression / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).clamp(-1.0, 1.0).asin();
+        let mut merged: Vec<(f64, f64)> = Vec::with_capacity(values.len().min(self.compression as usize));
+        let mut before = 0.0;
+
+        values.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (mean, weight) in values {
+            if let Some((last_mean, last_weight)) = merged.last_mut() {
+                let combined = *last_weight + weight;
+
+                if scale((before + combined) / total) - scale(before / total) <= 1.0 {
+                    *last_mean += (mean - *last_mean) * weight / combined;
+                    *last_weight = combined;
+                    continue;
+                }
+
+                before += *last_weight;
+            }
+
+            merged.push((mean, weight));
+        }
+
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use crate::aggregate::QuantileAggregator;
+    use super::*;
+
+    #[test]
+    fn matches_exact() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(20);
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(0.1));
+        let mut digest = DecayedTDigest::new(decay, 100.0);
+        let mut exact = QuantileAggregator::new(decay);
+
+        for i in 0..20_000u64 {
+            // A drifting signal, so the decayed quantiles differ from the undecayed ones.
+            let value = ((i * 7919) % 1000) as f64 + i as f64 / 20.0;
+            let timestamp = landmark + Duration::from_millis(i);
+
+            digest.add(timestamp, value);
+            exact.update((timestamp, value));
+        }
+
+        digest.compress();
+
+        assert!(digest.centroids() <= 200, "expected at most 200 centroids, got {}", digest.centroids());
+        assert!((digest.count(now) - exact.count(now)).abs() < 1e-9 * exact.count(now));
+
+        for phi in [0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99] {
+            let estimate = digest.quantile(phi, now);
+            let rank = exact.percentile_rank(estimate, now);
+            // The rank error of a t-digest shrinks towards the tails.
+            let bound = 0.01 + 4.0 * phi * (1.0 - phi) / digest.compression();
+
+            assert!((rank - phi).abs() < bound, "expected a rank within {bound} of {phi}, got {rank} for {estimate}");
+        }
+    }
+
+    #[test]
+    fn merge() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(0.2));
+        let mut left = DecayedTDigest::new(decay, 50.0);
+        let mut right = DecayedTDigest::new(decay, 50.0);
+        let mut combined = DecayedTDigest::new(decay, 50.0);
+
+        for i in 0..5000u64 {
+            let timestamp = landmark + Duration::from_millis(2 * i);
+            let value = ((i * 31) % 500) as f64;
+
+            if i % 2 == 0 { left.add(timestamp, value) } else { right.add(timestamp, value) }
+            combined.add(timestamp, value);
+        }
+
+        left.merge(&right).unwrap();
+
+        assert!((left.count(now) - combined.count(now)).abs() < 1e-9 * combined.count(now));
+
+        for phi in [0.1, 0.5, 0.9] {
+            assert!((left.quantile(phi, now) - combined.quantile(phi, now)).abs() < 10.0);
+        }
+
+        let other = DecayedTDigest::new(ForwardDecay::new(landmark + Duration::from_secs(1), g::Exponential::new(0.2)), 50.0);
+
+        assert_eq!(left.merge(&other), Err(Error::LandmarkMismatch));
+    }
+
+    #[test]
+    fn update_landmark() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut digest = DecayedTDigest::new(ForwardDecay::new(landmark, g::Exponential::new(0.5)), 100.0);
+
+        for i in 0..2000u64 {
+            digest.add(landmark + Duration::from_millis(5 * i), (i % 100) as f64);
+        }
+
+        let control = digest.clone();
+
+        digest.update_landmark(landmark + Duration::from_secs(8));
+
+        let epsilon = 1e-9;
+
+        assert!((digest.count(now) - control.count(now)).abs() < epsilon * control.count(now));
+        assert!((digest.quantile(0.5, now) - control.quantile(0.5, now)).abs() < epsilon);
+    }
+
+    #[test]
+    fn empty() {
+        let digest = DecayedTDigest::new(ForwardDecay::new(Instant::now(), ()), 100.0);
+
+        assert!(digest.quantile(0.5, Instant::now()).is_nan());
+        assert_eq!(digest.quantile_finite(0.5, Instant::now()), None);
+    }
+}
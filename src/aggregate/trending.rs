@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Instant;
+use crate::ForwardDecay;
+use crate::aggregate::Aggregator;
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// An aggregation computation over a stream of elements that ranks them by a blend of decayed frequency and recency.
+///
+/// The score of an element e at time t is c(e, t) + λ · w(last(e), t),
+/// where c(e, t) is the decayed count of the element, w(last(e), t) is the decayed weight of its most recent hit,
+/// and λ is the tunable recency weight.
+/// The recency bonus lies in [0, 1], so λ is the bonus in counts an element hit at exactly time t receives.
+/// A λ of 0 ranks by decayed count alone.
+///
+/// Every distinct element is tracked, so memory grows with the number of distinct elements until the next reset.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{TrendingAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(1));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let mut aggregator = TrendingAggregator::new(decay, 2.0);
+///
+/// aggregator.update(("old", landmark + Duration::from_secs(2)));
+/// aggregator.update(("new", landmark + Duration::from_secs(9)));
+///
+/// assert_eq!(aggregator.trending(2, now), vec![&"new", &"old"]);
+/// ```
+pub struct TrendingAggregator<E, G> {
+    decay: ForwardDecay<G>,
+    recency_weight: f64,
+    elements: HashMap<E, (f64, Instant)>,
+}
+
+impl<E, G> Aggregator for TrendingAggregator<E, G> where E: Hash + Eq + Clone, G: Function {
+    type Item = (E, Instant);
+    /// The element with the highest blended score, if any.
+    type Output = Option<E>;
+
+    fn update(&mut self, (element, timestamp): (E, Instant)) {
+        let static_weight = self.decay.static_weight(timestamp);
+        let (count, last) = self.elements.entry(element).or_insert((0.0, timestamp));
+
+        *count += static_weight;
+        *last = (*last).max(timestamp);
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+        self.elements.clear();
+    }
+
+    fn query(&self, timestamp: Instant) -> Option<E> {
+        self.trending(1, timestamp).first().map(|element| (*element).clone())
+    }
+}
+
+impl<E> TrendingAggregator<E, Exponential>
+where
+    E: Hash + Eq,
+{
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        for (count, _) in self.elements.values_mut() {
+            *count /= factor;
+        }
+    }
+}
+
+impl<E> SyncLandmark for TrendingAggregator<E, Exponential>
+where
+    E: Hash + Eq,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<E, G> TrendingAggregator<E, G>
+where
+    E: Hash + Eq,
+    G: Function,
+{
+    /// Initializes a new aggregator with the given decay model and recency weight λ.
+    pub fn new(decay: ForwardDecay<G>, recency_weight: f64) -> Self {
+        Self {
+            decay,
+            recency_weight,
+            elements: Default::default(),
+        }
+    }
+
+    /// The blended score of the given element at the given time, if it has been seen.
+    #[must_use]
+    pub fn score(&self, element: &E, timestamp: Instant) -> Option<f64> {
+        let factor = self.decay.normalizing_factor(timestamp);
+        let (count, last) = self.elements.get(element)?;
+
+        Some(self.blend(*count, *last, factor))
+    }
+
+    /// The k elements with the highest blended scores at the given time, in descending order.
+    /// Elements whose score is NaN, i.e. at a time where the normalizing factor is 0, are not ranked.
+    #[must_use]
+    pub fn trending(&self, k: usize, timestamp: Instant) -> Vec<&E> {
+        let factor = self.decay.normalizing_factor(timestamp);
+        let mut scores: Vec<(f64, &E)> = self.elements.iter()
+            .map(|(element, (count, last))| (self.blend(*count, *last, factor), element))
+            .filter(|(score, _)| !score.is_nan())
+            .collect();
+
+        scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scores.into_iter().take(k).map(|(_, element)| element).collect()
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+
+    fn blend(&self, count: f64, last: Instant, factor: f64) -> f64 {
+        (count + self.recency_weight * self.decay.static_weight(last)) / factor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    #[test]
+    fn spike_outranks_history() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(1));
+        let mut frequency = TrendingAggregator::new(fd, 0.0);
+        let mut trending = TrendingAggregator::new(fd, 10.0);
+
+        for _ in 0..20 {
+            frequency.update(("history", landmark + Duration::from_secs(5)));
+            trending.update(("history", landmark + Duration::from_secs(5)));
+        }
+
+        for _ in 0..8 {
+            frequency.update(("spike", landmark + Duration::from_secs(9)));
+            trending.update(("spike", landmark + Duration::from_secs(9)));
+        }
+
+        assert_eq!(frequency.trending(2, now), vec![&"history", &"spike"]);
+        assert_eq!(trending.trending(2, now), vec![&"spike", &"history"]);
+        assert_eq!(trending.score(&"history", now), Some(15.0));
+        assert_eq!(trending.trending(1, now), vec![&"spike"]);
+    }
+
+    #[test]
+    fn nan_scores() {
+        let landmark = Instant::now();
+        let mut trending = TrendingAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)), 10.0);
+
+        trending.update(("landmark", landmark));
+        trending.update(("later", landmark + Duration::from_secs(5)));
+
+        // At the landmark the normalizing factor is 0, so an element with no weight has a NaN score and is not ranked.
+        assert!(trending.score(&"landmark", landmark).is_some_and(f64::is_nan));
+        assert_eq!(trending.trending(2, landmark), vec![&"later"]);
+        assert_eq!(trending.trending(2, landmark + Duration::from_secs(10)), vec![&"later", &"landmark"]);
+    }
+}
@@ -0,0 +1,337 @@
+use std::marker::PhantomData;
+use std::time::Instant;
+use crate::{Error, ForwardDecay, Item};
+use crate::aggregate::{Aggregator, Checkpoint, CheckpointData};
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// The raw static accumulators of a [VarianceAggregator], relative to the landmark.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VarianceState {
+    pub count: f64,
+    pub sum: f64,
+    pub sum_squares: f64,
+    pub weight_squares: f64,
+    pub landmark: Instant,
+}
+
+/// Decayed aggregate mean and variance over a stream of items.
+///
+/// Alongside the decayed sums, the aggregator tracks the sum of squared weights,
+/// which gives the effective sample size (Σw)² / Σw² used to approximate the standard error of the mean.
+/// Items with a NaN value are ignored.
+///
+/// ## Examples
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{VarianceAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let stream = vec![
+///     (landmark + Duration::from_secs(5), 4.0),
+///     (landmark + Duration::from_secs(7), 8.0),
+///     (landmark + Duration::from_secs(3), 3.0),
+///     (landmark + Duration::from_secs(8), 6.0),
+///     (landmark + Duration::from_secs(4), 4.0),
+/// ];
+///
+/// let mut aggregator = VarianceAggregator::new(decay);
+///
+/// for item in stream {
+///     aggregator.update(item);
+/// }
+///
+/// let (mean, lower, upper) = aggregator.mean_with_interval(now, 1.96);
+///
+/// assert!(lower < mean && mean < upper);
+/// assert!((mean - aggregator.mean()).abs() < 0.0001);
+/// ```
+#[derive(Copy, Clone)]
+pub struct VarianceAggregator<G, I> {
+    decay: ForwardDecay<G>,
+    count: f64,
+    sum: f64,
+    sum_squares: f64,
+    weight_squares: f64,
+    _phantom_data: PhantomData<I>
+}
+
+impl<G, I> Aggregator for VarianceAggregator<G, I> where G: Function, I: Item {
+    type Item = I;
+    /// The decayed mean and variance, which do not depend on the query time.
+    type Output = (f64, f64);
+
+    fn update(&mut self, item: I) {
+        let value = item.value();
+
+        if value.is_nan() {
+            return;
+        }
+
+        let static_weight = self.decay.static_weight(&item);
+
+        self.count += static_weight;
+        self.sum += static_weight * value;
+        self.sum_squares += static_weight * value * value;
+        self.weight_squares += static_weight * static_weight;
+    }
+
+    fn reset(&mut self, landmark: Instant) {
+        self.decay.set_landmark(landmark);
+        self.count = 0.0;
+        self.sum = 0.0;
+        self.sum_squares = 0.0;
+        self.weight_squares = 0.0;
+    }
+
+    fn query(&self, _: Instant) -> (f64, f64) {
+        (self.mean(), self.variance())
+    }
+}
+
+impl<I> VarianceAggregator<Exponential, I>
+where
+    I: Item,
+{
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        self.count /= factor;
+        self.sum /= factor;
+        self.sum_squares /= factor;
+        self.weight_squares /= factor * factor;
+    }
+}
+
+impl<I> SyncLandmark for VarianceAggregator<Exponential, I>
+where
+    I: Item,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<G, I> VarianceAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    pub fn new(decay: ForwardDecay<G>) -> Self {
+        Self {
+            decay,
+            count: 0.0,
+            sum: 0.0,
+            sum_squares: 0.0,
+            weight_squares: 0.0,
+            _phantom_data: Default::default()
+        }
+    }
+
+    /// The decayed weighted mean of the values.
+    #[must_use]
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count
+    }
+
+    /// The decayed weighted mean, or [None] when it is not finite, i.e. before any items with a positive weight,
+    /// or once the static weights overflow under exponential decay far from the landmark.
+    #[must_use]
+    pub fn mean_finite(&self) -> Option<f64> {
+        Some(self.mean()).filter(|mean| mean.is_finite())
+    }
+
+    /// The decayed weighted (population) variance of the values.
+    #[must_use]
+    pub fn variance(&self) -> f64 {
+        let mean = self.mean();
+
+        (self.sum_squares / self.count - mean * mean).max(0.0)
+    }
+
+    /// The decayed weighted variance, or [None] when it is not finite. See [VarianceAggregator::mean_finite].
+    #[must_use]
+    pub fn variance_finite(&self) -> Option<f64> {
+        // The variance clamps a NaN difference to 0, so check the mean first.
+        self.mean_finite()
+            .map(|_| self.variance())
+            .filter(|variance| variance.is_finite())
+    }
+
+    /// The effective sample size (Σw)² / Σw² of the decayed weights.
+    /// Equals the number of items when all weights are equal, and shrinks as the weights become more skewed.
+    #[must_use]
+    pub fn effective_sample_size(&self) -> f64 {
+        (self.count * self.count) / self.weight_squares
+    }
+
+    /// The decayed mean along with the lower and upper bounds of an approximate confidence interval,
+    /// given the z-score for the desired confidence level (i.e. 1.96 for 95%).
+    /// The standard error is estimated from the decayed variance and the effective sample size.
+    /// Like the mean, the interval is a ratio of static accumulators, so it does not depend on the query time.
+    #[must_use]
+    pub fn mean_with_interval(&self, _: Instant, z: f64) -> (f64, f64, f64) {
+        let mean = self.mean();
+        let margin = z * (self.variance() / self.effective_sample_size()).sqrt();
+
+        (mean, mean - margin, mean + margin)
+    }
+
+    /// The raw static accumulators of this aggregator, before normalization.
+    pub fn static_state(&self) -> VarianceState {
+        VarianceState {
+            count: self.count,
+            sum: self.sum,
+            sum_squares: self.sum_squares,
+            weight_squares: self.weight_squares,
+            landmark: self.decay.landmark(),
+        }
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+}
+
+impl<G, I> Checkpoint<G> for VarianceAggregator<G, I>
+where
+    G: Function,
+    I: Item,
+{
+    /// Captures the static count, sum, sum of squares and sum of squared weights.
+    fn checkpoint(&self, reference: Instant) -> CheckpointData {
+        CheckpointData::new(self.decay.landmark(), reference, vec![self.count, self.sum, self.sum_squares, self.weight_squares])
+    }
+
+    fn restore(data: &CheckpointData, mut decay: ForwardDecay<G>, reference: Instant) -> Result<Self, Error> {
+        let [count, sum, sum_squares, weight_squares] = data.values()?;
+
+        decay.set_landmark(data.landmark(reference)?);
+
+        Ok(Self {
+            decay,
+            count,
+            sum,
+            sum_squares,
+            weight_squares,
+            _phantom_data: Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::g;
+    use super::*;
+
+    fn interval(values: &[f64]) -> (f64, f64, f64) {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(values.len() as u64 + 1);
+        let mut aggregator = VarianceAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        for (index, value) in values.iter().enumerate() {
+            aggregator.update((landmark + Duration::from_secs(index as u64 + 1), *value));
+        }
+
+        aggregator.mean_with_interval(now, 1.96)
+    }
+
+    #[test]
+    fn narrow_interval() {
+        let (mean, lower, upper) = interval(&[10.0, 10.1, 9.9, 10.0, 10.1, 9.9, 10.0, 10.0]);
+
+        assert!((mean - 10.0).abs() < 0.1);
+        assert!(upper - lower < 0.2);
+    }
+
+    #[test]
+    fn wide_interval() {
+        let (mean, lower, upper) = interval(&[1.0, 20.0, 3.0, 15.0, 0.0, 18.0, 5.0, 19.0]);
+
+        assert!(lower < mean && mean < upper);
+        assert!(upper - lower > 5.0);
+    }
+
+    #[test]
+    fn static_state() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut aggregator = VarianceAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(2)));
+
+        for (offset, value) in [(5, 4.0), (7, 8.0), (3, 3.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        let state = aggregator.static_state();
+        let mean = state.sum / state.count;
+
+        assert_eq!(state.landmark, landmark);
+        assert_eq!(mean, aggregator.mean());
+        assert_eq!((state.sum_squares / state.count - mean * mean).max(0.0), aggregator.variance());
+        assert_eq!((state.count * state.count) / state.weight_squares, aggregator.effective_sample_size());
+        assert!((aggregator.mean_with_interval(now, 1.0).0 - mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn uniform_weights() {
+        let landmark = Instant::now();
+        let mut aggregator = VarianceAggregator::new(ForwardDecay::new(landmark, ()));
+
+        for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            aggregator.update((landmark, value));
+        }
+
+        assert_eq!(aggregator.mean(), 5.0);
+        assert_eq!(aggregator.variance(), 4.0);
+        assert_eq!(aggregator.effective_sample_size(), 8.0);
+    }
+
+    #[test]
+    fn empty() {
+        let aggregator: VarianceAggregator<_, (Instant, f64)> = VarianceAggregator::new(ForwardDecay::new(Instant::now(), ()));
+
+        assert!(aggregator.mean().is_nan());
+        assert_eq!(aggregator.mean_finite(), None);
+        assert_eq!(aggregator.variance_finite(), None);
+    }
+
+    #[test]
+    fn interval_at_landmark() {
+        let landmark = Instant::now();
+        let mut aggregator = VarianceAggregator::new(ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        for (offset, value) in [(1, 4.0), (2, 8.0), (3, 3.0)] {
+            aggregator.update((landmark + Duration::from_secs(offset), value));
+        }
+
+        // The normalizing factor is 0 at the landmark, but it cancels out of the interval.
+        let (mean, lower, upper) = aggregator.mean_with_interval(landmark, 1.96);
+
+        assert_eq!(mean, aggregator.mean());
+        assert!(lower.is_finite() && upper.is_finite());
+        assert!(lower < mean && mean < upper);
+    }
+
+    #[test]
+    fn skips_nan() {
+        let landmark = Instant::now();
+        let mut aggregator = VarianceAggregator::new(ForwardDecay::new(landmark, ()));
+
+        for value in [2.0, f64::NAN, 4.0] {
+            aggregator.update((landmark, value));
+        }
+
+        assert_eq!(aggregator.mean(), 3.0);
+        assert_eq!(aggregator.variance(), 1.0);
+        assert_eq!(aggregator.effective_sample_size(), 2.0);
+    }
+}
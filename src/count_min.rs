@@ -0,0 +1,285 @@
+//! An implementation of the [Count-Min sketch](http://dimacs.rutgers.edu/~graham/pubs/papers/cm-full.pdf) for approximate frequencies.
+//! The sketch is adjusted to support the [forward decay model](http://dimacs.rutgers.edu/~graham/pubs/papers/fwddecay.pdf)
+//! by adding the static weight of each hit to its cells, and normalizing at query time.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use crate::ForwardDecay;
+use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// A Count-Min sketch over decayed hit counts.
+///
+/// With a width of ⌈e / ε⌉ and a depth of ⌈ln(1 / δ)⌉, the estimated count of an element overestimates
+/// the true decayed count by at most ε times the total decayed hits with a probability of at least 1 − δ.
+/// Estimates never underestimate, unless elements are removed that were never hit.
+///
+/// The conservative update variant only increments the cells that hold the current minimum estimate for an element.
+/// This retains the same guarantees while greatly reducing the overestimation for skewed streams,
+/// since the counts of frequent elements no longer inflate every cell they share with infrequent ones.
+/// However, removing an element from a conservative sketch may cause other elements to be underestimated.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::ForwardDecay;
+/// use fermentation::count_min::CountMinSketch;
+/// use fermentation::g::Exponential;
+///
+/// let decay = ForwardDecay::new(Instant::now(), Exponential::new(0.1));
+/// let landmark = decay.landmark();
+/// let now = landmark + Duration::from_secs(10);
+/// let mut sketch = CountMinSketch::conservative(64, 4, decay);
+///
+/// sketch.hit_at(&"a", landmark + Duration::from_secs(10));
+/// sketch.hit_at(&"a", landmark + Duration::from_secs(10));
+/// sketch.hit_at(&"b", landmark + Duration::from_secs(10));
+///
+/// assert!(sketch.get(&"a", now) >= 2.0);
+/// assert!(sketch.get(&"b", now) >= 1.0);
+///
+/// sketch.remove_at(&"b", landmark + Duration::from_secs(10));
+///
+/// assert!(sketch.get(&"b", now) < 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CountMinSketch<E, G> {
+    width: usize,
+    depth: usize,
+    conservative: bool,
+    decay: ForwardDecay<G>,
+    hits: f64,
+    cells: Vec<f64>,
+    _phantom_data: PhantomData<E>,
+}
+
+impl<E> CountMinSketch<E, Exponential>
+where
+    E: Hash,
+{
+    pub fn update_landmark(&mut self, landmark: Instant) {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        self.hits /= factor;
+
+        for cell in self.cells.iter_mut() {
+            *cell /= factor;
+        }
+    }
+}
+
+impl<E> SyncLandmark for CountMinSketch<E, Exponential>
+where
+    E: Hash,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
+    }
+}
+
+impl<E, G> CountMinSketch<E, G>
+where
+    E: Hash,
+    G: Function,
+{
+    /// Initializes a new sketch with the given number of counters per row (width) and number of rows (depth).
+    ///
+    /// ## Panic
+    /// Panics when either the width or the depth is 0.
+    pub fn new(width: usize, depth: usize, decay: ForwardDecay<G>) -> Self {
+        if width == 0 || depth == 0 {
+            panic!("width and depth must be greater than 0, given {width} and {depth}");
+        }
+
+        Self {
+            width,
+            depth,
+            conservative: false,
+            decay,
+            hits: 0.0,
+            cells: vec![0.0; width * depth],
+            _phantom_data: Default::default(),
+        }
+    }
+
+    /// Initializes a new sketch that uses conservative updates.
+    ///
+    /// ## Panic
+    /// Panics when either the width or the depth is 0.
+    pub fn conservative(width: usize, depth: usize, decay: ForwardDecay<G>) -> Self {
+        Self {
+            conservative: true,
+            ..Self::new(width, depth, decay)
+        }
+    }
+
+    /// Increments the given element's count by a single hit at the current time.
+    pub fn hit(&mut self, element: &E) {
+        self.hit_at(element, Instant::now())
+    }
+
+    /// Increments the given element's count by a single hit at the given time.
+    pub fn hit_at(&mut self, element: &E, timestamp: Instant) {
+        let weight = self.decay.static_weight(timestamp);
+        let indices = self.indices(element);
+
+        self.hits += weight;
+
+        if self.conservative {
+            let target = self.estimate(&indices) + weight;
+
+            for index in indices {
+                self.cells[index] = self.cells[index].max(target);
+            }
+        } else {
+            for index in indices {
+                self.cells[index] += weight;
+            }
+        }
+    }
+
+    /// Retracts a single hit for the given element at the current time.
+    pub fn remove(&mut self, element: &E) {
+        self.remove_at(element, Instant::now())
+    }
+
+    /// Retracts a single hit for the given element that occurred at the given time.
+    /// Cells are clamped at zero, so retracting a hit that was never recorded cannot produce negative counts.
+    pub fn remove_at(&mut self, element: &E, timestamp: Instant) {
+        let weight = self.decay.static_weight(timestamp);
+
+        self.hits = (self.hits - weight).max(0.0);
+
+        for index in self.indices(element) {
+            self.cells[index] = (self.cells[index] - weight).max(0.0);
+        }
+    }
+
+    /// The estimated decayed count for the given element.
+    #[must_use]
+    pub fn get(&self, element: &E, timestamp: Instant) -> f64 {
+        self.estimate(&self.indices(element)) / self.decay.normalizing_factor(timestamp)
+    }
+
+    /// The total decayed hits across all elements.
+    #[must_use]
+    pub fn hits(&self, timestamp: Instant) -> f64 {
+        self.hits / self.decay.normalizing_factor(timestamp)
+    }
+
+    pub fn decay(&self) -> &ForwardDecay<G> {
+        &self.decay
+    }
+
+    fn estimate(&self, indices: &[usize]) -> f64 {
+        indices.iter().map(|index| self.cells[*index]).fold(f64::INFINITY, f64::min)
+    }
+
+    fn indices(&self, element: &E) -> Vec<usize> {
+        (0..self.depth)
+            .map(|row| {
+                let mut hasher = DefaultHasher::new();
+
+                row.hash(&mut hasher);
+                element.hash(&mut hasher);
+
+                row * self.width + (hasher.finish() % self.width as u64) as usize
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn conservative_update() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(1);
+        let mut standard = CountMinSketch::new(16, 3, ForwardDecay::new(landmark, ()));
+        let mut conservative = CountMinSketch::conservative(16, 3, ForwardDecay::new(landmark, ()));
+
+        // A skewed stream where element i is hit 200 / (i + 1) times.
+        let counts: Vec<(u32, usize)> = (0..64).map(|i| (i, 200 / (i as usize + 1))).collect();
+
+        for (element, count) in counts.iter() {
+            for _ in 0..*count {
+                standard.hit_at(element, now);
+                conservative.hit_at(element, now);
+            }
+        }
+
+        let mut standard_error = 0.0;
+        let mut conservative_error = 0.0;
+
+        for (element, count) in counts.iter() {
+            let actual = *count as f64;
+
+            assert!(standard.get(element, now) >= actual);
+            assert!(conservative.get(element, now) >= actual);
+            assert!(conservative.get(element, now) <= standard.get(element, now));
+
+            standard_error += standard.get(element, now) - actual;
+            conservative_error += conservative.get(element, now) - actual;
+        }
+
+        assert!(conservative_error < standard_error);
+        assert_eq!(standard.hits(now), conservative.hits(now));
+    }
+
+    #[test]
+    fn remove() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(1);
+        let mut sketch = CountMinSketch::new(32, 4, ForwardDecay::new(landmark, ()));
+
+        sketch.hit_at(&"a", now);
+        sketch.hit_at(&"a", now);
+        sketch.remove_at(&"a", now);
+
+        assert_eq!(sketch.get(&"a", now), 1.0);
+        assert_eq!(sketch.hits(now), 1.0);
+
+        sketch.remove_at(&"a", now);
+        sketch.remove_at(&"a", now);
+
+        assert_eq!(sketch.get(&"a", now), 0.0);
+        assert_eq!(sketch.hits(now), 0.0);
+    }
+
+    #[test]
+    fn update_landmark() {
+        let landmark = Instant::now();
+        let new_landmark = landmark + Duration::from_secs(5);
+        let now = landmark + Duration::from_secs(10);
+        let decay = ForwardDecay::new(landmark, Exponential::new(0.2));
+        let mut sketch = CountMinSketch::conservative(32, 4, decay);
+        let mut control = CountMinSketch::conservative(32, 4, ForwardDecay::new(new_landmark, Exponential::new(0.2)));
+
+        for (element, offset) in [("a", 3), ("b", 6), ("a", 8), ("c", 9)] {
+            sketch.hit_at(&element, landmark + Duration::from_secs(offset));
+            control.hit_at(&element, landmark + Duration::from_secs(offset));
+        }
+
+        sketch.update_landmark(new_landmark);
+
+        let epsilon = 0.0001;
+
+        for element in ["a", "b", "c"] {
+            assert!((sketch.get(&element, now) - control.get(&element, now)).abs() < epsilon);
+        }
+
+        assert!((sketch.hits(now) - control.hits(now)).abs() < epsilon);
+    }
+}
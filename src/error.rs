@@ -0,0 +1,27 @@
+use std::fmt::{Display, Formatter};
+
+/// The errors that may occur when aggregating a stream of items.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Error {
+    /// An item arrived before the landmark, by the given number of seconds.
+    PreLandmark(f64),
+    /// Aggregates relative to different landmarks cannot be combined.
+    LandmarkMismatch,
+    /// Aggregates computed with different decay functions or time scales cannot be combined.
+    DecayMismatch,
+    /// A checkpoint does not hold the state of the aggregator it is restored into.
+    InvalidCheckpoint,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::PreLandmark(age) => write!(f, "item arrived {} seconds before the landmark", -age),
+            Error::LandmarkMismatch => write!(f, "aggregates have different landmarks"),
+            Error::DecayMismatch => write!(f, "aggregates have different decay functions"),
+            Error::InvalidCheckpoint => write!(f, "checkpoint does not match the aggregator"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,129 @@
+//! Helpers for exporting decayed aggregates to external metrics systems.
+
+use std::time::Instant;
+
+use crate::aggregate::{Accumulator, BasicAggregator};
+use crate::g::Function;
+use crate::Item;
+
+/// Emits the decayed sum, count and average of a [BasicAggregator] as gauges
+/// via the [metrics](https://docs.rs/metrics) crate facade, named `{name}_sum`, `{name}_count` and `{name}_average`.
+///
+/// Decayed values change continuously as time passes, even without new items,
+/// so the gauges are only as current as the last call to [MetricsExporter::emit].
+/// The caller is responsible for emitting at a suitable cadence, i.e. before each scrape or on a timer.
+#[derive(Clone, Debug)]
+pub struct MetricsExporter {
+    sum: String,
+    count: String,
+    average: String,
+}
+
+impl MetricsExporter {
+    /// Creates an exporter for gauges with the given name prefix and describes them to the installed recorder.
+    pub fn new(name: &str) -> Self {
+        let exporter = Self {
+            sum: format!("{name}_sum"),
+            count: format!("{name}_count"),
+            average: format!("{name}_average"),
+        };
+
+        metrics::describe_gauge!(exporter.sum.clone(), "The decayed sum of the values.");
+        metrics::describe_gauge!(exporter.count.clone(), "The decayed count of the values.");
+        metrics::describe_gauge!(exporter.average.clone(), "The decayed average of the values.");
+
+        exporter
+    }
+
+    /// Sets the gauges to the aggregator's current decayed values at the given time.
+    pub fn emit<G, I, A>(&self, aggregator: &BasicAggregator<G, I, A>, timestamp: Instant)
+    where
+        G: Function,
+        I: Item,
+        A: Accumulator,
+    {
+        metrics::gauge!(self.sum.clone()).set(aggregator.sum(timestamp));
+        metrics::gauge!(self.count.clone()).set(aggregator.count(timestamp));
+        metrics::gauge!(self.average.clone()).set(aggregator.average());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use metrics::{Counter, Gauge, GaugeFn, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+
+    use crate::ForwardDecay;
+    use crate::aggregate::Aggregator;
+    use crate::g;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Capture(Arc<Mutex<HashMap<String, f64>>>);
+
+    struct CapturedGauge(String, Arc<Mutex<HashMap<String, f64>>>);
+
+    impl GaugeFn for CapturedGauge {
+        fn increment(&self, value: f64) {
+            *self.1.lock().unwrap().entry(self.0.clone()).or_default() += value;
+        }
+
+        fn decrement(&self, value: f64) {
+            *self.1.lock().unwrap().entry(self.0.clone()).or_default() -= value;
+        }
+
+        fn set(&self, value: f64) {
+            self.1.lock().unwrap().insert(self.0.clone(), value);
+        }
+    }
+
+    impl Recorder for Capture {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, _: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::noop()
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(Arc::new(CapturedGauge(key.name().to_string(), self.0.clone())))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn emit() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let mut aggregator = BasicAggregator::new(fd);
+
+        aggregator.update((landmark + Duration::from_secs(5), 4.0));
+        aggregator.update((landmark + Duration::from_secs(7), 8.0));
+
+        let recorder = Capture::default();
+        let values = recorder.0.clone();
+
+        metrics::with_local_recorder(&recorder, || {
+            let exporter = MetricsExporter::new("latency");
+
+            exporter.emit(&aggregator, now);
+        });
+
+        let values = values.lock().unwrap();
+
+        assert_eq!(values.get("latency_sum"), Some(&aggregator.sum(now)));
+        assert_eq!(values.get("latency_count"), Some(&aggregator.count(now)));
+        assert_eq!(values.get("latency_average"), Some(&aggregator.average()));
+    }
+}
@@ -1,17 +1,36 @@
 //! Various implementations of positive monotone non-decreasing functions, used to calculate the decayed weight of an item.
 
 use std::time::Duration;
+use crate::math;
 
 /// A positive monotone non-decreasing function g, used to calculate the decayed weight of an item.
 /// Implementors are responsible for ensuring the range of the function adheres to these requirements.
 pub trait Function {
     fn invoke(&self, age: f64) -> f64;
+
+    /// The rate of change g'(n) of the function at the given age, i.e. for sensitivity analysis of the weights.
+    ///
+    /// The default approximates the derivative numerically with a central difference,
+    /// using a step proportional to the age to balance truncation and rounding error.
+    /// Implementors with a closed form should override it.
+    fn derivative(&self, age: f64) -> f64 {
+        // The cube root of the machine epsilon, which minimizes the total error of a central difference.
+        const STEP: f64 = 6.055454452393343e-6;
+
+        let step = STEP * age.abs().max(1.0);
+
+        (self.invoke(age + step) - self.invoke(age - step)) / (2.0 * step)
+    }
 }
 
 impl Function for () {
     fn invoke(&self, _: f64) -> f64 {
         1.0
     }
+
+    fn derivative(&self, _: f64) -> f64 {
+        0.0
+    }
 }
 
 /// Exponential decay: g(n) = exp(α * n) for parameter α > 0.
@@ -22,7 +41,7 @@ impl Exponential {
     /// ## Panic
     /// Panics when alpha is not greater than 0.
     pub fn new(alpha: f64) -> Self {
-        if !(alpha > 0.0) {
+        if alpha.is_nan() || alpha <= 0.0 {
             panic!("alpha must be greater than 0, given {alpha}");
         }
 
@@ -47,40 +66,91 @@ impl Exponential {
             panic!("target must in the range (0, 1), given {target}");
         }
 
-        Self(-target.ln() / duration.as_secs_f64())
+        Self(-math::ln(target) / duration.as_secs_f64())
+    }
+
+    /// The time it takes for the weight of an item to halve, ln(2) / α seconds.
+    /// The inverse of constructing the function with [Exponential::rate] and a target of 0.5.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::Duration;
+    /// use fermentation::g::Exponential;
+    ///
+    /// assert_eq!(Exponential::new(2.0_f64.ln() / 30.0).half_life(), Duration::from_secs(30));
+    /// ```
+    pub fn half_life(&self) -> Duration {
+        Duration::from_secs_f64(math::ln(2.0) / self.0)
     }
 }
 
 impl Function for Exponential {
     fn invoke(&self, age: f64) -> f64 {
-        (self.0 * age).exp()
+        math::exp(self.0 * age)
+    }
+
+    /// g'(n) = α * exp(α * n).
+    fn derivative(&self, age: f64) -> f64 {
+        self.0 * math::exp(self.0 * age)
     }
 }
 
 /// Polynomial decay: g(n) = n ^ β for some parameter β > 0.
-#[derive(Copy, Clone)]
+///
+/// Note that g itself grows with age; the decay comes from the ratio w(i, t) = g(ti − L) / g(t − L).
+/// Since t ≥ ti, an item's weight starts at 1 on arrival and shrinks towards 0 as the query time moves forward.
+/// A negative β would invert this relationship, giving older items a weight greater than 1, so it is rejected.
+///
+/// ## Example
+/// ```rust
+/// use fermentation::g::{Function, Polynomial};
+///
+/// let g = Polynomial::new(2);
+///
+/// // Between ages 5 and 10 the function grows 4-fold, so an item of age 5 has a weight of 0.25 at age 10.
+/// assert_eq!(g.growth_rate(5.0, 10.0), 4.0);
+/// assert_eq!(g.invoke(5.0) / g.invoke(10.0), 0.25);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Polynomial(i32);
 
 impl Polynomial {
     /// ## Panic
     /// Panics when beta is not greater than 0.
     pub fn new(beta: i32) -> Self {
-        if !(beta > 0) {
-            panic!("beta must be greater than 0, given {beta}");
+        Self::try_new(beta).unwrap_or_else(|| panic!("beta must be greater than 0, given {beta}"))
+    }
+
+    /// Creates a polynomial decay function, or returns [None] when beta is not greater than 0.
+    /// A positive beta guarantees that the weight g(ti − L) / g(t − L) stays within [0, 1] for any L ≤ ti ≤ t.
+    pub fn try_new(beta: i32) -> Option<Self> {
+        if beta > 0 {
+            Some(Self(beta))
+        } else {
+            None
         }
+    }
 
-        Self(beta)
+    /// The factor g(later) / g(earlier) by which the function grows between two ages.
+    /// An item with an age of `earlier` has a weight of the reciprocal of this factor when queried at an age of `later`.
+    pub fn growth_rate(&self, earlier: f64, later: f64) -> f64 {
+        self.invoke(later) / self.invoke(earlier)
     }
 }
 
 impl Function for Polynomial {
     fn invoke(&self, age: f64) -> f64 {
-        age.powi(self.0)
+        math::powi(age, self.0)
+    }
+
+    /// g'(n) = β * n ^ (β - 1).
+    fn derivative(&self, age: f64) -> f64 {
+        self.0 as f64 * math::powi(age, self.0 - 1)
     }
 }
 
 /// Landmark Window: g(n) = 1 for n > 0, and 0 otherwise.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct LandmarkWindow;
 
 impl Function for LandmarkWindow {
@@ -93,6 +163,180 @@ impl Function for LandmarkWindow {
     }
 }
 
+/// Symmetric weighting: g(n) = f(|n|) for an inner function f.
+/// Items equidistant before and after the landmark receive the same weight,
+/// which is useful for smoothing kernels centered on a reference point.
+///
+/// Note that this is not a standard forward decay function, since it is not monotone non-decreasing over negative ages.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Symmetric<F>(F);
+
+impl<F> Symmetric<F> where F: Function {
+    pub fn new(inner: F) -> Self {
+        Self(inner)
+    }
+}
+
+impl<F> Function for Symmetric<F> where F: Function {
+    fn invoke(&self, age: f64) -> f64 {
+        self.0.invoke(age.abs())
+    }
+
+    fn derivative(&self, age: f64) -> f64 {
+        self.0.derivative(age.abs()) * age.signum()
+    }
+}
+
+/// Hard expiry: g(n) = f(n) for n ≤ TTL, and exactly 0 beyond it, for an inner function f.
+///
+/// Smooth decay such as exponential decay only approaches zero asymptotically,
+/// whereas compliance requirements may demand that data is fully forgotten after a fixed TTL.
+/// The cutoff breaks the smoothness (and, for a non-increasing inner function, the continuity) of the weights,
+/// trading it for a guarantee that items older than the TTL contribute nothing.
+///
+/// The cutoff applies to the age the function is invoked with.
+/// With a [BackwardDecay](crate::BackwardDecay) that is the age of each item at query time, which gives the hard-deletion semantics.
+/// A forward decay model instead invokes g with ages relative to the landmark, where a cutoff would drop newer items
+/// and zero the normalizing factor, so expiring the static aggregates of a forward decay model requires a reset instead.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{BackwardDecay, g};
+///
+/// let now = Instant::now();
+/// let exponential = g::Custom::new(|age: f64| (-0.1 * age).exp());
+/// let decay = BackwardDecay::new(g::Expiring::new(Duration::from_secs(60), exponential));
+///
+/// assert_eq!(decay.weight(now - Duration::from_secs(10), now), (-1.0_f64).exp());
+/// assert_eq!(decay.weight(now - Duration::from_secs(61), now), 0.0);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Expiring<F> {
+    ttl: f64,
+    inner: F,
+}
+
+impl<F> Expiring<F> where F: Function {
+    pub fn new(ttl: Duration, inner: F) -> Self {
+        Self {
+            ttl: ttl.as_secs_f64(),
+            inner,
+        }
+    }
+
+    /// The age in seconds beyond which the weight is 0.
+    pub fn ttl(&self) -> f64 {
+        self.ttl
+    }
+}
+
+impl<F> Function for Expiring<F> where F: Function {
+    fn invoke(&self, age: f64) -> f64 {
+        if age > self.ttl {
+            0.0
+        } else {
+            self.inner.invoke(age)
+        }
+    }
+
+    /// The derivative of the inner function up to the TTL, and 0 beyond it, ignoring the discontinuity at the cutoff.
+    fn derivative(&self, age: f64) -> f64 {
+        if age > self.ttl {
+            0.0
+        } else {
+            self.inner.derivative(age)
+        }
+    }
+}
+
+/// Blends two functions at an age threshold: g(n) = below(n) for n ≤ threshold, and c · above(n) beyond it.
+///
+/// The constant c = below(threshold) / above(threshold) makes the weights continuous at the seam,
+/// so an item just past the threshold weighs the same as one exactly at it.
+/// Scaling does not change the relative weights of items beyond the threshold,
+/// i.e. exponential decay above the threshold still halves weights at the same rate.
+///
+/// With a forward decay model, ages are relative to the landmark, so items near the landmark are the oldest.
+/// To stop decaying retained items older than some age, use no decay below the threshold and exponential decay above it.
+///
+/// ## Example
+/// ```rust
+/// use fermentation::g::{Exponential, Function, Piecewise};
+///
+/// let exponential = Exponential::new(0.1);
+/// let g = Piecewise::new(60.0, (), exponential);
+///
+/// assert_eq!(g.invoke(30.0), 1.0);
+/// assert_eq!(g.invoke(60.0), 1.0);
+/// assert!((g.invoke(70.0) - exponential.invoke(10.0)).abs() < 1e-9);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Piecewise<A, B> {
+    threshold: f64,
+    below: A,
+    above: B,
+    scale: f64,
+}
+
+impl<A, B> Piecewise<A, B> where A: Function, B: Function {
+    /// ## Panic
+    /// Panics when the threshold is not finite, when `above` is not positive at the threshold,
+    /// or when either function is decreasing at the seam, since the blend would not be monotone non-decreasing.
+    pub fn new(threshold: f64, below: A, above: B) -> Self {
+        if !threshold.is_finite() {
+            panic!("threshold must be finite, given {threshold}");
+        }
+
+        let seam = above.invoke(threshold);
+        if seam.is_nan() || seam <= 0.0 || seam.is_infinite() {
+            panic!("above must be positive at the threshold, given {seam}");
+        }
+
+        let (left, right) = (below.derivative(threshold), above.derivative(threshold));
+        if left.is_nan() || left < 0.0 || right.is_nan() || right < 0.0 {
+            panic!("functions must be non-decreasing at the threshold, given derivatives {left} below and {right} above");
+        }
+
+        let scale = below.invoke(threshold) / seam;
+
+        Self { threshold, below, above, scale }
+    }
+
+    /// The age in seconds at which the function switches from below to above.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn below(&self) -> &A {
+        &self.below
+    }
+
+    pub fn above(&self) -> &B {
+        &self.above
+    }
+}
+
+impl<A, B> Function for Piecewise<A, B> where A: Function, B: Function {
+    fn invoke(&self, age: f64) -> f64 {
+        if age <= self.threshold {
+            self.below.invoke(age)
+        } else {
+            self.scale * self.above.invoke(age)
+        }
+    }
+
+    /// The derivative of the selected function, scaled like its value.
+    /// The function is continuous at the seam, but its derivative may jump.
+    fn derivative(&self, age: f64) -> f64 {
+        if age <= self.threshold {
+            self.below.derivative(age)
+        } else {
+            self.scale * self.above.derivative(age)
+        }
+    }
+}
+
 /// Wraps any arbitrary struct that implements the [Fn] trait to be used with a forward decay model.
 /// Implementors are responsible for ensuring the range of the function is positive, monotone and non-decreasing.
 #[derive(Copy, Clone)]
@@ -116,6 +360,157 @@ impl<F> Function for Custom<F> where F: Fn(f64) -> f64 {
     }
 }
 
+/// One of the built-in functions, as a component of a [CompositeFunction].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Builtin {
+    /// No decay, like `()`: g(n) = 1.
+    Constant,
+    Exponential(Exponential),
+    Polynomial(Polynomial),
+    LandmarkWindow,
+}
+
+impl From<()> for Builtin {
+    fn from(_: ()) -> Self {
+        Self::Constant
+    }
+}
+
+impl From<Exponential> for Builtin {
+    fn from(function: Exponential) -> Self {
+        Self::Exponential(function)
+    }
+}
+
+impl From<Polynomial> for Builtin {
+    fn from(function: Polynomial) -> Self {
+        Self::Polynomial(function)
+    }
+}
+
+impl From<LandmarkWindow> for Builtin {
+    fn from(_: LandmarkWindow) -> Self {
+        Self::LandmarkWindow
+    }
+}
+
+impl Function for Builtin {
+    fn invoke(&self, age: f64) -> f64 {
+        match self {
+            Self::Constant => ().invoke(age),
+            Self::Exponential(function) => function.invoke(age),
+            Self::Polynomial(function) => function.invoke(age),
+            Self::LandmarkWindow => LandmarkWindow.invoke(age),
+        }
+    }
+
+    /// The derivative of the built-in function, taking the landmark window to be flat on either side of its step.
+    fn derivative(&self, age: f64) -> f64 {
+        match self {
+            Self::Constant | Self::LandmarkWindow => 0.0,
+            Self::Exponential(function) => function.derivative(age),
+            Self::Polynomial(function) => function.derivative(age),
+        }
+    }
+}
+
+/// How a [CompositeFunction] combines its components.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Combinator {
+    /// g(n) = f1(n) × f2(n) × ...
+    Product,
+    /// g(n) = f1(n) + f2(n) + ...
+    Sum,
+    /// g(n) = min(f1(n), f2(n), ...)
+    Min,
+}
+
+/// A combination of a fixed number of built-in functions, without boxing or closures,
+/// so that it is [Copy] and keeps a [ForwardDecay](crate::ForwardDecay) cheaply copyable.
+/// The product, sum and minimum of positive monotone non-decreasing functions are positive monotone non-decreasing as well.
+///
+/// ## Example
+/// ```rust
+/// use fermentation::g::{self, CompositeFunction, Function};
+///
+/// // Exponential decay restricted to items after the landmark.
+/// let exponential = g::Exponential::new(0.1);
+/// let g = CompositeFunction::product([exponential.into(), g::LandmarkWindow.into()]);
+///
+/// assert_eq!(g.invoke(10.0), exponential.invoke(10.0));
+/// assert_eq!(g.invoke(-10.0), 0.0);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CompositeFunction<const N: usize> {
+    combinator: Combinator,
+    functions: [Builtin; N],
+}
+
+impl<const N: usize> CompositeFunction<N> {
+    /// ## Panic
+    /// Panics when there are no functions to combine.
+    pub fn new(combinator: Combinator, functions: [Builtin; N]) -> Self {
+        if N == 0 {
+            panic!("a composite function requires at least 1 function");
+        }
+
+        Self { combinator, functions }
+    }
+
+    /// The product of the given functions. See [CompositeFunction::new].
+    pub fn product(functions: [Builtin; N]) -> Self {
+        Self::new(Combinator::Product, functions)
+    }
+
+    /// The sum of the given functions. See [CompositeFunction::new].
+    pub fn sum(functions: [Builtin; N]) -> Self {
+        Self::new(Combinator::Sum, functions)
+    }
+
+    /// The minimum of the given functions. See [CompositeFunction::new].
+    pub fn min(functions: [Builtin; N]) -> Self {
+        Self::new(Combinator::Min, functions)
+    }
+
+    pub fn combinator(&self) -> Combinator {
+        self.combinator
+    }
+
+    pub fn functions(&self) -> &[Builtin; N] {
+        &self.functions
+    }
+}
+
+impl<const N: usize> Function for CompositeFunction<N> {
+    fn invoke(&self, age: f64) -> f64 {
+        let values = self.functions.iter().map(|function| function.invoke(age));
+
+        match self.combinator {
+            Combinator::Product => values.product(),
+            Combinator::Sum => values.sum(),
+            Combinator::Min => values.fold(f64::INFINITY, f64::min),
+        }
+    }
+
+    /// Combines the derivatives of the components with the product and sum rules,
+    /// or takes the derivative of the smallest component for the minimum.
+    fn derivative(&self, age: f64) -> f64 {
+        match self.combinator {
+            Combinator::Product => (0..N)
+                .map(|i| {
+                    self.functions.iter().enumerate()
+                        .map(|(j, function)| if i == j { function.derivative(age) } else { function.invoke(age) })
+                        .product::<f64>()
+                })
+                .sum(),
+            Combinator::Sum => self.functions.iter().map(|function| function.derivative(age)).sum(),
+            Combinator::Min => self.functions.iter()
+                .min_by(|a, b| a.invoke(age).total_cmp(&b.invoke(age)))
+                .map_or(0.0, |function| function.derivative(age)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,10 +524,36 @@ mod tests {
 
     #[test]
     fn exponential() {
+        // libm rounds differently from the platform's math library; see the libm test for its tolerance.
+        #[cfg(not(feature = "libm"))]
         assert_eq!(Exponential::new(1.0).invoke(1.0), 1.0_f64.exp());
         assert_eq!(Exponential::rate(0.0001, Duration::from_secs(60)), Exponential::new(0.1535056728662697));
     }
 
+    #[test]
+    fn subsecond_exponential() {
+        let epsilon = 1e-9;
+        let millis = Exponential::rate(0.5, Duration::from_millis(250));
+        let micros = Exponential::rate(0.5, Duration::from_micros(250));
+
+        assert!((millis.invoke(0.25) - 2.0).abs() < epsilon);
+        assert!((millis.invoke(0.5) - 4.0).abs() < epsilon);
+        assert!((micros.invoke(0.000_25) - 2.0).abs() < epsilon);
+        assert!((micros.invoke(0.000_5) - millis.invoke(0.5)).abs() < epsilon);
+        assert!((Exponential::rate(0.5, Duration::from_millis(500)).invoke(0.5) - Exponential::rate(0.5, Duration::from_secs(1)).invoke(1.0)).abs() < epsilon);
+    }
+
+    #[test]
+    fn half_life() {
+        let epsilon = Duration::from_nanos(1);
+
+        for duration in [Duration::from_millis(250), Duration::from_secs(30), Duration::from_secs(86_400)] {
+            let half_life = Exponential::rate(0.5, duration).half_life();
+
+            assert!(half_life.abs_diff(duration) <= epsilon, "expected {duration:?}, got {half_life:?}");
+        }
+    }
+
     #[test]
     #[should_panic]
     fn negative_exponential() {
@@ -145,6 +566,18 @@ mod tests {
         Exponential::new(0.0);
     }
 
+    #[test]
+    fn expiring() {
+        let exponential = Custom::new(|age: f64| (-0.5 * age).exp());
+        let expiring = Expiring::new(Duration::from_millis(1500), exponential);
+
+        assert_eq!(expiring.ttl(), 1.5);
+        assert_eq!(expiring.invoke(1.0), exponential.invoke(1.0));
+        assert_eq!(expiring.invoke(1.5), exponential.invoke(1.5));
+        assert_eq!(expiring.invoke(1.5 + f64::EPSILON), 0.0);
+        assert_eq!(expiring.invoke(1e9), 0.0);
+    }
+
     #[test]
     fn polynomial() {
         assert_eq!(Polynomial::new(3).invoke(2.0), 8.0);
@@ -162,6 +595,24 @@ mod tests {
         Polynomial::new(0);
     }
 
+    #[test]
+    fn try_polynomial() {
+        assert!(Polynomial::try_new(1).is_some());
+        assert!(Polynomial::try_new(0).is_none());
+        assert!(Polynomial::try_new(-2).is_none());
+    }
+
+    #[test]
+    fn polynomial_direction() {
+        let g = Polynomial::new(2);
+
+        assert!(g.invoke(1.0) < g.invoke(2.0));
+        assert_eq!(g.growth_rate(2.0, 4.0), 4.0);
+        assert_eq!(g.growth_rate(4.0, 4.0), 1.0);
+        assert_eq!(1.0 / g.growth_rate(2.0, 4.0), 0.25);
+        assert!(g.growth_rate(4.0, 2.0) < 1.0);
+    }
+
     #[test]
     fn landmark() {
         assert_eq!(LandmarkWindow.invoke(1.0), 1.0);
@@ -175,4 +626,138 @@ mod tests {
         assert_eq!(Custom::from(|n| n * 0.2).invoke(0.0), 0.0);
         assert_eq!(Custom::from(|n| n * 0.2).invoke(-1.0), -0.2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn symmetric() {
+        let polynomial = Symmetric::new(Polynomial::new(3));
+        let exponential = Symmetric::new(Exponential::new(1.0));
+
+        assert_eq!(polynomial.invoke(2.0), 8.0);
+        assert_eq!(polynomial.invoke(-2.0), 8.0);
+        assert_eq!(exponential.invoke(-1.5), exponential.invoke(1.5));
+        assert_eq!(exponential.invoke(0.0), 1.0);
+    }
+
+    #[test]
+    fn derivative() {
+        struct Numerical<F>(F);
+
+        impl<F> Function for Numerical<F> where F: Function {
+            fn invoke(&self, age: f64) -> f64 {
+                self.0.invoke(age)
+            }
+        }
+
+        let relative_epsilon = 1e-6;
+
+        for age in [0.5, 1.0, 3.0, 10.0, 60.0] {
+            for function in [Exponential::new(0.1), Exponential::new(1.0), Exponential::rate(0.5, Duration::from_millis(250))] {
+                let (exact, numerical) = (function.derivative(age), Numerical(function).derivative(age));
+
+                assert!((exact - numerical).abs() <= relative_epsilon * exact, "expected {exact}, got {numerical} at age {age}");
+            }
+
+            for function in [Polynomial::new(1), Polynomial::new(2), Polynomial::new(5)] {
+                let (exact, numerical) = (function.derivative(age), Numerical(function).derivative(age));
+
+                assert!((exact - numerical).abs() <= relative_epsilon * exact, "expected {exact}, got {numerical} at age {age}");
+            }
+        }
+
+        assert_eq!(Exponential::new(2.0).derivative(0.0), 2.0);
+        assert_eq!(Polynomial::new(2).derivative(3.0), 6.0);
+        assert_eq!(Polynomial::new(1).derivative(7.0), 1.0);
+        assert_eq!(().derivative(7.0), 0.0);
+    }
+
+    #[test]
+    fn composite() {
+        let exponential = Exponential::new(0.1);
+        let windowed = CompositeFunction::product([exponential.into(), LandmarkWindow.into()]);
+        // Both the function and the decay model are Copy, so they remain usable after being copied.
+        let decay = crate::ForwardDecay::new(std::time::Instant::now(), windowed);
+        let copied = decay;
+
+        assert_eq!(copied.g(), decay.g());
+        assert_eq!(*decay.g(), windowed);
+        assert_eq!(windowed.invoke(5.0), exponential.invoke(5.0));
+        assert_eq!(windowed.invoke(-5.0), 0.0);
+        assert_eq!(windowed.invoke(0.0), 0.0);
+        assert_eq!(windowed.derivative(5.0), exponential.derivative(5.0));
+
+        let sum = CompositeFunction::sum([Polynomial::new(2).into(), ().into()]);
+        let min = CompositeFunction::min([Polynomial::new(1).into(), Polynomial::new(2).into(), exponential.into()]);
+
+        assert_eq!(sum.invoke(3.0), 10.0);
+        assert_eq!(sum.derivative(3.0), 6.0);
+        assert_eq!(min.invoke(0.5), 0.25);
+        assert_eq!(min.invoke(2.0), 2.0_f64.min(exponential.invoke(2.0)));
+        assert_eq!(min.derivative(0.5), 1.0);
+        assert_eq!(min.combinator(), Combinator::Min);
+    }
+
+    #[test]
+    #[cfg(feature = "libm")]
+    fn libm() {
+        use std::time::Instant;
+        use crate::ForwardDecay;
+
+        let epsilon = 1e-12;
+        let exponential = Exponential::new(0.3);
+        let polynomial = Polynomial::new(3);
+
+        for age in [0.0, 0.5, 1.0, 7.25, 100.0] {
+            assert!((exponential.invoke(age) / (0.3 * age).exp() - 1.0).abs() < epsilon);
+            assert!((polynomial.invoke(age) - age.powi(3)).abs() <= epsilon * age.powi(3));
+        }
+
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let item = landmark + Duration::from_secs(4);
+
+        assert!((ForwardDecay::new(landmark, exponential).weight(item, now) - (-1.8_f64).exp()).abs() < epsilon);
+        assert!((ForwardDecay::new(landmark, polynomial).weight(item, now) - 0.064).abs() < epsilon);
+        assert!((Exponential::rate(0.5, Duration::from_secs(30)).half_life().as_secs_f64() - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn piecewise() {
+        let exponential = Exponential::new(0.1);
+        let flat = Piecewise::new(60.0, (), exponential);
+        let epsilon = 1e-9;
+
+        assert_eq!(flat.invoke(0.0), 1.0);
+        assert_eq!(flat.invoke(60.0), 1.0);
+        assert_eq!(flat.derivative(30.0), 0.0);
+        assert!((flat.invoke(80.0) - exponential.invoke(20.0)).abs() < epsilon);
+        assert!((flat.derivative(80.0) - exponential.derivative(20.0)).abs() < epsilon);
+
+        // Continuous across the seam, from both directions.
+        let polynomial = Piecewise::new(10.0, Polynomial::new(2), exponential);
+
+        assert_eq!(polynomial.invoke(10.0), 100.0);
+        assert!((polynomial.invoke(10.0 + 1e-9) - 100.0).abs() < 1e-6);
+        assert!((polynomial.invoke(10.0 - 1e-9) - 100.0).abs() < 1e-6);
+        assert_eq!(polynomial.invoke(5.0), 25.0);
+        assert!(polynomial.invoke(20.0) > polynomial.invoke(10.0));
+        assert_eq!(polynomial.threshold(), 10.0);
+
+        let mut previous = 0.0;
+        for age in (0..200).map(|age| age as f64 * 0.1) {
+            assert!(polynomial.invoke(age) >= previous);
+            previous = polynomial.invoke(age);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "non-decreasing at the threshold")]
+    fn piecewise_decreasing() {
+        let _ = Piecewise::new(10.0, Exponential::new(0.1), Custom::new(|age: f64| 1.0 / age));
+    }
+
+    #[test]
+    #[should_panic(expected = "positive at the threshold")]
+    fn piecewise_zero_seam() {
+        let _ = Piecewise::new(0.0, (), Polynomial::new(1));
+    }
+}
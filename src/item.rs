@@ -1,4 +1,5 @@
 use std::time::{Duration, Instant};
+use crate::landmark;
 
 /// An item in a stream of inputs.
 pub trait Item {
@@ -6,22 +7,39 @@ pub trait Item {
     fn timestamp(&self) -> Instant;
 
     /// The age in seconds (including fractional time) for this item.
+    ///
+    /// Ages are represented as 64-bit floats, so their resolution shrinks as they grow:
+    /// at an age of A seconds the smallest representable difference is roughly A × 2.2e-16,
+    /// i.e. about a nanosecond after 52 days and about a microsecond after 143 years.
+    /// Sub-second differences between items far from the landmark may therefore lose precision;
+    /// moving the landmark forward periodically keeps ages small.
     fn age(&self, landmark: Instant) -> f64;
 
     /// The value associated with this item.
     fn value(&self) -> f64;
 }
 
+/// An item with an importance that is independent of time, such as a sampling rate or a priority.
+/// Aggregators that support importance multiply the static weight of an item by its importance,
+/// so an item with an importance of 2 contributes as much as two identical items with the default importance of 1.
+///
+/// Implementing the trait without overriding [WeightedItem::importance] gives every item the default importance.
+pub trait WeightedItem: Item {
+    /// The importance of this item, independent of its age.
+    fn importance(&self) -> f64 {
+        1.0
+    }
+}
+
 impl Item for Instant {
     fn timestamp(&self) -> Instant {
         *self
     }
 
+    /// At most one of the saturating differences is non-zero, so the age never panics regardless of the order of the instants,
+    /// and is exactly 0 when they are equal.
     fn age(&self, landmark: Instant) -> f64 {
-        self.checked_duration_since(landmark)
-            .as_ref()
-            .map(Duration::as_secs_f64)
-            .unwrap_or_else(|| -1.0 * landmark.duration_since(*self).as_secs_f64())
+        self.saturating_duration_since(landmark).as_secs_f64() - landmark.saturating_duration_since(*self).as_secs_f64()
     }
 
     fn value(&self) -> f64 {
@@ -44,6 +62,82 @@ impl Item for (Instant, f64) {
     }
 }
 
+impl WeightedItem for Instant {}
+
+impl WeightedItem for (Instant, f64) {}
+
+/// A (timestamp, value, importance) item.
+impl Item for (Instant, f64, f64) {
+    fn timestamp(&self) -> Instant {
+        self.0
+    }
+
+    fn age(&self, landmark: Instant) -> f64 {
+        self.0.age(landmark)
+    }
+
+    fn value(&self) -> f64 {
+        self.1
+    }
+}
+
+impl WeightedItem for (Instant, f64, f64) {
+    fn importance(&self) -> f64 {
+        self.2
+    }
+}
+
+/// An (age, value) item, whose age is given directly in seconds since the aggregator's landmark instead of as an [Instant].
+/// Lets precomputed ages act as items without [Instant] plumbing, i.e. when replaying recorded data.
+///
+/// The age is returned as is for any landmark, so the item always has the given age.
+/// Recovering a timestamp requires knowing the landmark, which [Item::timestamp] is not given,
+/// so the timestamp is synthesized as [landmark::origin] plus the age, saturating at the origin
+/// for ages that are not representable. It is only meaningful when the landmark is the origin,
+/// i.e. `landmark::Elapsed(Duration::ZERO)`; elsewhere, options that depend on timestamps,
+/// such as strict ordering or the last update time, see the synthesized timestamps instead.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{Aged, ForwardDecay, g};
+/// use fermentation::aggregate::{BasicAggregator, Aggregator};
+///
+/// let decay = ForwardDecay::new(Instant::now(), g::Polynomial::new(2));
+/// let mut aggregator = BasicAggregator::new(decay);
+///
+/// aggregator.update(Aged(5.0, 4.0));
+/// aggregator.update(Aged(7.0, 8.0));
+///
+/// assert_eq!(aggregator.sum(decay.landmark() + Duration::from_secs(10)), 4.0 * 0.25 + 8.0 * 0.49);
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Aged(pub f64, pub f64);
+
+impl Item for Aged {
+    fn timestamp(&self) -> Instant {
+        let origin = landmark::origin();
+        let age = Duration::try_from_secs_f64(self.0.abs()).unwrap_or(Duration::MAX);
+
+        if self.0 >= 0.0 {
+            origin.checked_add(age)
+        } else {
+            origin.checked_sub(age)
+        }
+        .unwrap_or(origin)
+    }
+
+    fn age(&self, _: Instant) -> f64 {
+        self.0
+    }
+
+    fn value(&self) -> f64 {
+        self.1
+    }
+}
+
+impl WeightedItem for Aged {}
+
 impl<I> Item for &I
 where
     I: Item,
@@ -59,4 +153,142 @@ where
     fn value(&self) -> f64 {
         (*self).value()
     }
-}
\ No newline at end of file
+}
+
+impl<I> WeightedItem for &I
+where
+    I: WeightedItem,
+{
+    fn importance(&self) -> f64 {
+        (*self).importance()
+    }
+}
+
+#[cfg(feature = "chrono")]
+static ANCHOR: std::sync::OnceLock<landmark::ClockAnchor> = std::sync::OnceLock::new();
+
+/// The mapping between the monotonic clock and wall-clock time used by every chrono conversion in this process.
+/// It is captured once, the first time it is needed, so conversions are deterministic:
+/// the same timestamps always map to the same instants, and ages do not depend on when they are computed.
+#[cfg(feature = "chrono")]
+fn anchor() -> &'static landmark::ClockAnchor {
+    ANCHOR.get_or_init(landmark::ClockAnchor::now)
+}
+
+/// Converts a wall-clock timestamp to the monotonic clock through the process's [landmark::ClockAnchor].
+///
+/// ## Panic
+/// Panics when the timestamp precedes the origin of the monotonic clock.
+#[cfg(feature = "chrono")]
+pub(crate) fn from_date_time(timestamp: chrono::DateTime<chrono::Utc>) -> Instant {
+    anchor().to_instant(timestamp.into())
+}
+
+/// Converts a monotonic timestamp to wall-clock time through the process's [landmark::ClockAnchor].
+#[cfg(feature = "chrono")]
+pub(crate) fn to_date_time(timestamp: Instant) -> chrono::DateTime<chrono::Utc> {
+    anchor().to_system_time(timestamp).into()
+}
+
+/// A wall-clock timestamped item.
+/// Ages are computed in wall-clock time, by mapping the landmark onto wall-clock time
+/// through a [landmark::ClockAnchor] captured once per process, so the age of an item never changes.
+///
+/// Unlike [Instant], wall-clock time is not monotonic: it may jump backwards or forwards when the system clock is adjusted,
+/// which silently changes the ages of items relative to the landmark.
+/// Prefer [Instant] timestamps when items are produced in the same process.
+///
+/// ## Panic
+/// [Item::timestamp] panics when the timestamp precedes the origin of the monotonic clock.
+#[cfg(feature = "chrono")]
+impl Item for (chrono::DateTime<chrono::Utc>, f64) {
+    fn timestamp(&self) -> Instant {
+        from_date_time(self.0)
+    }
+
+    fn age(&self, landmark: Instant) -> f64 {
+        let age = self.0.signed_duration_since(to_date_time(landmark));
+
+        age.num_nanoseconds().map_or(age.num_seconds() as f64, |nanos| nanos as f64 / 1e9)
+    }
+
+    fn value(&self) -> f64 {
+        self.1
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl WeightedItem for (chrono::DateTime<chrono::Utc>, f64) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The earliest instant reachable by subtracting from the given one, approximating the origin of the monotonic clock.
+    fn earliest(mut instant: Instant) -> Instant {
+        let mut step = Duration::from_secs(u64::MAX / 2);
+
+        while !step.is_zero() {
+            match instant.checked_sub(step) {
+                Some(earlier) => instant = earlier,
+                None => step /= 2,
+            }
+        }
+
+        instant
+    }
+
+    #[test]
+    fn adversarial_ages() {
+        let now = Instant::now();
+        let origin = earliest(now);
+        let later = now + Duration::from_secs(100 * 365 * 86_400);
+
+        assert_eq!(now.age(now), 0.0);
+        assert!(now.age(now).is_sign_positive());
+        assert_eq!(origin.age(origin), 0.0);
+        assert!(now.age(origin) > 0.0);
+        assert!(origin.age(now) < 0.0);
+        assert_eq!(origin.age(now), -now.age(origin));
+        assert!(later.age(origin) > later.age(now));
+        assert!(origin.age(later) < 0.0);
+        assert_eq!((now + Duration::from_nanos(1)).age(now), 1e-9);
+        assert_eq!(now.age(now + Duration::from_nanos(1)), -1e-9);
+    }
+
+    #[test]
+    fn aged() {
+        let origin = landmark::origin();
+        let item = Aged(2.5, 4.0);
+
+        assert_eq!(item.timestamp(), origin + Duration::from_millis(2500));
+        assert_eq!(item.age(origin), 2.5);
+        assert_eq!(item.age(Instant::now()), 2.5);
+        assert_eq!(Aged(-1.0, 4.0).age(origin), -1.0);
+        assert!(Aged(-1.0, 4.0).timestamp() <= origin);
+    }
+
+    #[test]
+    fn aggregate_aged() {
+        use crate::ForwardDecay;
+        use crate::aggregate::{Aggregator, BasicAggregator};
+        use crate::g;
+
+        // The landmark is not the origin, so the ages must not be shifted by the landmark's distance from it.
+        let decay = ForwardDecay::new(Instant::now() + Duration::from_secs(60), g::Exponential::new(0.1));
+        let landmark = decay.landmark();
+        let now = landmark + Duration::from_secs(10);
+        let mut aged = BasicAggregator::new(decay);
+        let mut timestamped = BasicAggregator::new(decay);
+
+        for (age, value) in [(1.0, 3.0), (2.5, 4.0), (7.0, 1.0)] {
+            aged.update(Aged(age, value));
+            timestamped.update((landmark + Duration::from_secs_f64(age), value));
+        }
+
+        let epsilon = 1e-12;
+
+        assert!((aged.sum(now) - timestamped.sum(now)).abs() < epsilon);
+        assert!((aged.count(now) - timestamped.count(now)).abs() < epsilon);
+    }
+}
@@ -0,0 +1,372 @@
+//! Alternative representations of landmarks, convertible into an [Instant].
+
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+static ORIGIN: OnceLock<Instant> = OnceLock::new();
+
+/// The origin that [Elapsed] durations are measured from.
+/// The standard library does not expose the start time of the process,
+/// so the origin is fixed the first time it is needed, which approximates the start of the process
+/// closely when the first use happens during startup.
+pub fn origin() -> Instant {
+    *ORIGIN.get_or_init(Instant::now)
+}
+
+/// A landmark given as the elapsed time since the [origin], which lets test and replay code express landmarks
+/// as plain durations while production code passes [Instant]s.
+///
+/// ## Example
+/// ```rust
+/// use std::time::Duration;
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::landmark::{self, Elapsed};
+///
+/// let decay = ForwardDecay::new(Elapsed(Duration::from_secs(5)), g::Polynomial::new(2));
+///
+/// assert_eq!(decay.landmark(), landmark::origin() + Duration::from_secs(5));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Elapsed(pub Duration);
+
+impl From<Elapsed> for Instant {
+    fn from(value: Elapsed) -> Self {
+        origin() + value.0
+    }
+}
+
+/// A landmark shared by many aggregators, possibly across threads, which they follow by synchronizing with it.
+///
+/// The landmark is stored as an atomic count of nanoseconds since the landmark the value was created with,
+/// behind an [Arc], so clones are cheap handles to the same landmark.
+/// The landmark only moves forward; advancing it to an earlier time has no effect.
+///
+/// A [ForwardDecay](crate::ForwardDecay) does not reference the shared landmark: it is created with a snapshot of its current value,
+/// and keeps that snapshot until its aggregator is synchronized with [SyncLandmark::sync_landmark].
+/// Static aggregates must be rescaled whenever the landmark moves, which only the aggregator can do,
+/// so advancing the shared landmark has no effect on an aggregator until it is synchronized.
+/// Any aggregator whose landmark can move without losing its aggregates, i.e. under exponential decay, implements [SyncLandmark].
+///
+/// ## Memory ordering
+/// Advancing the landmark is a release operation and reading it is an acquire operation,
+/// so any writes made by a thread before it advances the landmark are visible to a thread that reads the new landmark.
+/// The landmark is a single atomic value, so every thread observes the same sequence of landmarks,
+/// although a thread may observe an advance later than others.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::landmark::SharedLandmark;
+///
+/// let shared = SharedLandmark::new(Instant::now());
+/// let decay = ForwardDecay::new(&shared, g::Exponential::new(0.1));
+/// let next = shared.get() + Duration::from_secs(5);
+///
+/// assert_eq!(decay.landmark(), shared.get());
+/// assert!(shared.advance(next));
+/// assert_eq!(shared.get(), next);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedLandmark {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    start: Instant,
+    ticks: AtomicU64,
+}
+
+impl SharedLandmark {
+    pub fn new(landmark: impl Into<Instant>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                start: landmark.into(),
+                ticks: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// The current landmark.
+    pub fn get(&self) -> Instant {
+        self.inner.start + Duration::from_nanos(self.inner.ticks.load(Ordering::Acquire))
+    }
+
+    /// Moves the landmark forward to the given time.
+    /// Returns whether the landmark moved, which is false when the given time is not later than the current landmark.
+    /// Durations beyond about 584 years since the initial landmark saturate.
+    pub fn advance(&self, landmark: impl Into<Instant>) -> bool {
+        let elapsed = landmark.into().saturating_duration_since(self.inner.start);
+        let ticks = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+
+        self.inner.ticks.fetch_max(ticks, Ordering::AcqRel) < ticks
+    }
+}
+
+/// A snapshot of the current landmark. See [SharedLandmark::get].
+impl From<&SharedLandmark> for Instant {
+    fn from(value: &SharedLandmark) -> Self {
+        value.get()
+    }
+}
+
+/// Aggregators whose landmark can be moved without losing their aggregates, i.e. under exponential decay,
+/// so that they can follow a [SharedLandmark].
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::aggregate::{BasicAggregator, QuantileAggregator};
+/// use fermentation::landmark::{SharedLandmark, SyncLandmark};
+///
+/// let shared = SharedLandmark::new(Instant::now());
+/// let decay = ForwardDecay::new(&shared, g::Exponential::new(0.1));
+/// let mut sum: BasicAggregator<_, (Instant, f64)> = BasicAggregator::new(decay);
+/// let mut quantile: QuantileAggregator<_, (Instant, f64)> = QuantileAggregator::new(decay);
+///
+/// shared.advance(shared.get() + Duration::from_secs(5));
+///
+/// assert!(sum.sync_landmark(&shared));
+/// assert!(quantile.sync_landmark(&shared));
+/// assert!(!sum.sync_landmark(&shared));
+/// assert_eq!(SyncLandmark::landmark(&quantile), shared.get());
+/// ```
+pub trait SyncLandmark {
+    /// The current landmark of this aggregator.
+    fn landmark(&self) -> Instant;
+
+    /// Moves the landmark, rescaling the aggregates so that queries are unchanged.
+    fn update_landmark(&mut self, landmark: Instant);
+
+    /// Moves the landmark to the current value of the shared landmark, if it changed.
+    /// Returns whether the landmark moved.
+    fn sync_landmark(&mut self, shared: &SharedLandmark) -> bool {
+        let landmark = shared.get();
+
+        if landmark == self.landmark() {
+            return false;
+        }
+
+        self.update_landmark(landmark);
+        true
+    }
+}
+
+/// A pairing of an [Instant] and a [SystemTime] observed at the same moment,
+/// which maps between a process's monotonic clock and the wall clock shared by every process.
+///
+/// Each process has its own [Instant] epoch, so static aggregates computed in different processes are not comparable directly.
+/// Anchoring the landmark to a wall-clock time lets every process agree on it:
+/// each process converts the shared [SystemTime] landmark with its own anchor,
+/// and rebases the states it receives from others with [AggregatorState::rebase](crate::aggregate::AggregatorState::rebase) before merging.
+/// Capture the anchor once per process, since conversions through different anchors differ by the time between their captures.
+///
+/// ## Clock skew
+/// Conversions assume that the wall clocks of all processes agree.
+/// A skew of δ seconds between two processes shifts the imported items by δ relative to the local ones,
+/// i.e. scaling their weights by exp(α × δ) under exponential decay, so keep the clocks synchronized (i.e. with NTP)
+/// to well within the time scale of the decay.
+/// The anchor is captured once, so later adjustments of the wall clock are not reflected in its conversions.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::landmark::ClockAnchor;
+///
+/// let anchor = ClockAnchor::now();
+/// let landmark = SystemTime::now() - Duration::from_secs(60);
+/// let decay = ForwardDecay::new(anchor.to_instant(landmark), g::Exponential::new(0.1));
+///
+/// assert_eq!(anchor.to_system_time(decay.landmark()), landmark);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ClockAnchor {
+    instant: Instant,
+    system_time: SystemTime,
+}
+
+impl ClockAnchor {
+    /// An anchor pairing the given instant with the given wall-clock time.
+    pub fn new(instant: Instant, system_time: SystemTime) -> Self {
+        Self { instant, system_time }
+    }
+
+    /// An anchor pairing the current instant with the current wall-clock time.
+    pub fn now() -> Self {
+        Self::new(Instant::now(), SystemTime::now())
+    }
+
+    /// The instant corresponding to the given wall-clock time.
+    ///
+    /// ## Panic
+    /// Panics when the wall-clock time precedes the origin of the monotonic clock.
+    pub fn to_instant(&self, system_time: SystemTime) -> Instant {
+        match system_time.duration_since(self.system_time) {
+            Ok(ahead) => self.instant + ahead,
+            Err(behind) => self.instant.checked_sub(behind.duration())
+                .unwrap_or_else(|| panic!("{system_time:?} precedes the origin of the monotonic clock")),
+        }
+    }
+
+    /// The wall-clock time corresponding to the given instant.
+    pub fn to_system_time(&self, instant: Instant) -> SystemTime {
+        match instant.checked_duration_since(self.instant) {
+            Some(ahead) => self.system_time + ahead,
+            None => self.system_time - self.instant.duration_since(instant),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+    use std::thread;
+    use crate::aggregate::AggregatorState;
+    use crate::{ForwardDecay, g};
+    use crate::aggregate::{Aggregator, BasicAggregator};
+    use super::*;
+
+    #[test]
+    fn advance() {
+        let landmark = Instant::now();
+        let shared = SharedLandmark::new(landmark);
+
+        assert!(!shared.advance(landmark));
+        assert!(shared.advance(landmark + Duration::from_secs(5)));
+        assert!(!shared.clone().advance(landmark + Duration::from_secs(3)));
+        assert_eq!(shared.get(), landmark + Duration::from_secs(5));
+    }
+
+    #[test]
+    fn coordinated_rescale() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let shared = SharedLandmark::new(landmark);
+        let barrier = Barrier::new(5);
+        let decay = ForwardDecay::new(&shared, g::Exponential::new(0.1));
+        let mut control = BasicAggregator::new(decay);
+
+        for offset in 1..=50 {
+            control.update((landmark + Duration::from_secs(offset), offset as f64));
+        }
+
+        control.update_landmark(landmark + Duration::from_secs(40));
+
+        let results: Vec<(Instant, f64)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..4)
+                .map(|_| {
+                    let shared = shared.clone();
+                    let barrier = &barrier;
+
+                    scope.spawn(move || {
+                        let mut aggregator = BasicAggregator::new(ForwardDecay::new(&shared, g::Exponential::new(0.1)));
+
+                        for offset in 1..=50 {
+                            aggregator.update((landmark + Duration::from_secs(offset), offset as f64));
+                        }
+
+                        barrier.wait();
+                        barrier.wait();
+                        aggregator.sync_landmark(&shared);
+
+                        (aggregator.decay().landmark(), aggregator.sum(now))
+                    })
+                })
+                .collect();
+
+            barrier.wait();
+            shared.advance(landmark + Duration::from_secs(40));
+            barrier.wait();
+
+            workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+        });
+
+        for (worker_landmark, sum) in results {
+            assert_eq!(worker_landmark, landmark + Duration::from_secs(40));
+            assert_eq!(sum, control.sum(now));
+        }
+    }
+
+    #[test]
+    fn sync_aggregators() {
+        use crate::aggregate::{QuantileAggregator, VarianceAggregator};
+        use crate::space_saving::BTreeSpaceSaving;
+
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let shared = SharedLandmark::new(landmark);
+        let decay = ForwardDecay::new(&shared, g::Exponential::new(0.1));
+        let mut sum = BasicAggregator::new(decay);
+        let mut variance = VarianceAggregator::new(decay);
+        let mut quantile = QuantileAggregator::new(decay);
+        let mut top = BTreeSpaceSaving::new(4, decay);
+
+        for offset in 1..=50 {
+            let item = (landmark + Duration::from_secs(offset), (offset % 7) as f64);
+
+            sum.update(item);
+            variance.update(item);
+            quantile.update(item);
+            top.hit_at(offset % 7, item.0);
+        }
+
+        let before = (sum.sum(now), variance.variance(), quantile.quantile(0.5, now), top.hits(now));
+
+        shared.advance(landmark + Duration::from_secs(40));
+
+        assert!(sum.sync_landmark(&shared));
+        assert!(variance.sync_landmark(&shared));
+        assert!(quantile.sync_landmark(&shared));
+        assert!(top.sync_landmark(&shared));
+
+        let after = (sum.sum(now), variance.variance(), quantile.quantile(0.5, now), top.hits(now));
+        let epsilon = 1e-9;
+
+        assert_eq!(SyncLandmark::landmark(&top), shared.get());
+        assert!((before.0 - after.0).abs() < epsilon * before.0);
+        assert!((before.1 - after.1).abs() < epsilon * before.1);
+        assert_eq!(before.2, after.2);
+        assert!((before.3 - after.3).abs() < epsilon * before.3);
+    }
+
+    #[test]
+    fn rebased_merge() {
+        let wall = SystemTime::now();
+        let base = Instant::now();
+        let decay = |landmark| ForwardDecay::new(landmark, g::Exponential::new(0.05));
+        // The same wall-clock time corresponds to different instants in each simulated process.
+        let anchors = [ClockAnchor::new(base + Duration::from_secs(1000), wall), ClockAnchor::new(base + Duration::from_secs(5), wall)];
+        let landmark = wall - Duration::from_secs(60);
+        let now = anchors[1].to_instant(wall + Duration::from_secs(60));
+        let items: Vec<(Duration, f64)> = (1..=100).map(|offset| (Duration::from_secs(offset), offset as f64)).collect();
+        let mut processes = anchors.map(|anchor| BasicAggregator::new(decay(anchor.to_instant(landmark))));
+        let mut control = BasicAggregator::new(decay(anchors[1].to_instant(landmark)));
+
+        for (index, (offset, value)) in items.iter().enumerate() {
+            let process = index % 2;
+
+            processes[process].update((anchors[process].to_instant(landmark + *offset), *value));
+            control.update((anchors[1].to_instant(landmark + *offset), *value));
+        }
+
+        let state: AggregatorState = processes[0].static_state();
+        let mut merged = processes[1];
+
+        assert!(merged.merge(&BasicAggregator::from_state(decay(state.landmark), state)).is_err());
+
+        let rebased = state.rebase(&anchors[0], &anchors[1]);
+
+        assert_eq!(rebased.landmark, merged.static_state().landmark);
+
+        merged.merge(&BasicAggregator::from_state(decay(rebased.landmark), rebased)).unwrap();
+
+        let epsilon = 1e-9;
+
+        assert!((merged.sum(now) - control.sum(now)).abs() < epsilon);
+        assert!((merged.count(now) - control.count(now)).abs() < epsilon);
+    }
+}
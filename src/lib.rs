@@ -4,13 +4,24 @@
 use std::time::Instant;
 
 pub mod aggregate;
+pub mod count_min;
+mod error;
+#[cfg(feature = "metrics")]
+pub mod export;
 pub mod g;
 mod item;
+pub mod landmark;
+mod math;
+mod random;
 pub mod space_saving;
+#[cfg(test)]
+mod testing;
 
-pub use item::Item;
+pub use error::Error;
+pub use item::{Aged, Item, WeightedItem};
 
 use crate::g::Function;
+use crate::random::Random;
 
 /// The forward decay is computed on the amount of time between the arrival of an item and a fixed point L,
 /// known as the landmark. By convention, this landmark is some time earlier than all other items;
@@ -89,6 +100,7 @@ use crate::g::Function;
 pub struct ForwardDecay<G> {
     landmark: Instant,
     g: G,
+    time_scale: f64,
 }
 
 impl<G> ForwardDecay<G>
@@ -96,13 +108,66 @@ where
     G: Function,
 {
     /// Create a new instance with a positive monotone non-decreasing function and a landmark time.
-    pub fn new(landmark: Instant, g: G) -> Self {
+    /// The landmark may be given as an [Instant] or as anything convertible into one, such as [landmark::Elapsed].
+    pub fn new(landmark: impl Into<Instant>, g: G) -> Self {
+        Self::with_time_scale(landmark, g, 1.0)
+    }
+
+    /// Create a new instance where ages are measured in units of the given number of seconds,
+    /// i.e. a time scale of 60 passes ages in minutes to g, and 3600 in hours.
+    ///
+    /// For exponential decay, a time scale of s is equivalent to dividing α by s,
+    /// since exp(α · (n / s)) = exp((α / s) · n).
+    /// For polynomial decay, the time scale cancels out of the weight g(ti − L) / g(t − L) entirely,
+    /// and only changes the magnitude of the static weights.
+    ///
+    /// ## Panic
+    /// Panics when the time scale is not greater than 0.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use std::time::{Duration, Instant};
+    /// use fermentation::{ForwardDecay, g};
+    ///
+    /// let landmark = Instant::now();
+    /// let now = landmark + Duration::from_secs(3 * 3600);
+    /// let item = landmark + Duration::from_secs(3600);
+    ///
+    /// // Decays by a factor of e every hour.
+    /// let decay = ForwardDecay::with_time_scale(landmark, g::Exponential::new(1.0), 3600.0);
+    ///
+    /// assert!((decay.weight(item, now) - (-2.0f64).exp()).abs() < 1e-12);
+    /// ```
+    pub fn with_time_scale(landmark: impl Into<Instant>, g: G, time_scale: f64) -> Self {
+        if time_scale.is_nan() || time_scale <= 0.0 {
+            panic!("time scale must be greater than 0, given {time_scale}");
+        }
+
         Self {
-            landmark,
+            landmark: landmark.into(),
             g,
+            time_scale,
         }
     }
 
+    /// Create a new instance with a wall-clock landmark time.
+    /// The landmark is mapped onto the monotonic clock through a [ClockAnchor](landmark::ClockAnchor) captured once per process,
+    /// the same one used to compute the ages of wall-clock timestamped items;
+    /// later adjustments of the system clock are not reflected in the landmark.
+    ///
+    /// ## Panic
+    /// Panics when the landmark precedes the origin of the monotonic clock.
+    #[cfg(feature = "chrono")]
+    pub fn from_date_time(landmark: chrono::DateTime<chrono::Utc>, g: G) -> Self {
+        Self::new(item::from_date_time(landmark), g)
+    }
+
+    /// The landmark for this decay model in wall-clock time, through the process's [ClockAnchor](landmark::ClockAnchor).
+    #[cfg(feature = "chrono")]
+    pub fn landmark_date_time(&self) -> chrono::DateTime<chrono::Utc> {
+        item::to_date_time(self.landmark)
+    }
+
     /// The function g for this decay model.
     pub fn g(&self) -> &G {
         &self.g
@@ -113,14 +178,33 @@ where
         self.landmark
     }
 
+    /// The number of seconds in a unit of age passed to g.
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
     /// Update the landmark to the given timestamp.
-    /// Returns the age of the new landmark relative to the previous landmark.
-    pub fn set_landmark(&mut self, landmark: Instant) -> f64 {
-        let age = landmark.age(self.landmark);
+    /// Returns the age of the new landmark relative to the previous landmark, in units of the time scale.
+    pub fn set_landmark(&mut self, landmark: impl Into<Instant>) -> f64 {
+        let landmark = landmark.into();
+        let age = self.scaled_age(landmark);
         self.landmark = landmark;
         age
     }
 
+    /// The age of the given timestamp relative to the landmark, in seconds (including fractional time).
+    /// The age is positive for timestamps after the landmark, zero at the landmark and negative before it.
+    pub fn landmark_age_at(&self, timestamp: Instant) -> f64 {
+        timestamp.age(self.landmark)
+    }
+
+    /// Whether the given timestamp is strictly after the landmark.
+    /// Queries at or before the landmark may divide by g(0) or by g of a negative age,
+    /// which can produce infinite, NaN or otherwise meaningless weights.
+    pub fn is_after_landmark(&self, timestamp: Instant) -> bool {
+        timestamp > self.landmark
+    }
+
     /// Given a positive monotone non-decreasing function g, and a landmark time L,
     /// the decayed weight of an item with arrival time ti > L measured at time t ≥ ti
     /// is given by w(i, t) = g(ti − L) / g(t − L).
@@ -128,7 +212,7 @@ where
     where
         I: Item,
     {
-        self.g.invoke(item.age(self.landmark)) / self.g.invoke(timestamp.age(self.landmark))
+        self.g.invoke(self.scaled_age(item)) / self.g.invoke(self.scaled_age(timestamp))
     }
 
     /// The value of this item multiplied by its weight.
@@ -145,7 +229,7 @@ where
     where
         I: Item,
     {
-        self.g.invoke(item.age(self.landmark))
+        self.g.invoke(self.scaled_age(&item))
     }
 
     /// The weighted value of the item without the normalizing factor of 1 / g(t - L).
@@ -154,7 +238,7 @@ where
     where
         I: Item,
     {
-        self.g.invoke(item.age(self.landmark)) * item.value()
+        self.g.invoke(self.scaled_age(&item)) * item.value()
     }
 
     /// In order to normalize values given that the function value increases with time,
@@ -162,7 +246,107 @@ where
     /// the function of the current time.
     pub fn normalizing_factor(&self, timestamp: Instant) -> f64
     {
-        self.g.invoke(timestamp.age(self.landmark))
+        self.g.invoke(self.scaled_age(timestamp))
+    }
+
+    /// Draws a sample of k distinct items, without replacement, where each item is chosen with probability
+    /// proportional to its decayed weight, using the [Efraimidis-Spirakis](https://doi.org/10.1016/j.ipl.2005.11.003) algorithm.
+    /// Returns the indices of the sampled items in the given slice.
+    /// When k is larger than the number of items, every index is returned.
+    ///
+    /// The static weight is used in place of the decayed weight, since the normalizing factor is the same for every item.
+    /// Items whose static weight is not positive, i.e. pre-landmark items under odd-degree polynomial decay, or NaN,
+    /// have no probability of being chosen and are never sampled, so fewer than k indices may be returned.
+    pub fn weighted_sample_without_replacement<I>(&self, items: &[I], k: usize) -> Vec<usize>
+    where
+        I: Item,
+    {
+        let mut random = Random::new();
+        let mut keys: Vec<(f64, usize)> = items.iter()
+            .enumerate()
+            .map(|(index, item)| (self.static_weight(item), index))
+            .filter(|(weight, _)| *weight > 0.0)
+            // The key u^(1/w) is compared in log space to avoid underflow for small weights.
+            .map(|(weight, index)| (math::ln(random.next_f64()) / weight, index))
+            .collect();
+
+        keys.sort_by(|a, b| b.0.total_cmp(&a.0));
+        keys.truncate(k);
+        keys.into_iter().map(|(_, index)| index).collect()
+    }
+
+    fn scaled_age<I>(&self, item: I) -> f64
+    where
+        I: Item,
+    {
+        item.age(self.landmark) / self.time_scale
+    }
+}
+
+/// In backward decay, the weight of an item is a function of its age a = t − ti, measured backward from the current time t.
+/// Given a positive monotone non-increasing function f with f(0) = 1,
+/// the decayed weight of an item with arrival time ti measured at time t ≥ ti is given by w(i, t) = f(t − ti).
+///
+/// Backward decay is provided for comparison with [ForwardDecay].
+/// Note that f must be non-increasing, unlike the function g used by forward decay.
+/// For exponential decay the two models coincide, since exp(−α(t − ti)) = exp(α(ti − L)) / exp(α(t − L)).
+/// For other functions they differ.
+///
+/// ## Numerical Issues
+/// Backward decay weights always lie between zero and one, so there is no risk of overflow.
+/// However, because the weight of every item changes as t moves forward,
+/// aggregates cannot in general be maintained incrementally;
+/// each query needs the age of every item relative to the query time.
+/// Only exponential decay avoids this, by scaling the running aggregate on every update.
+/// Forward decay instead keeps per-item weights fixed relative to the landmark and normalizes once at query time,
+/// at the cost of large intermediate values that may need rescaling.
+///
+/// ## Examples
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{BackwardDecay, g};
+///
+/// let now = Instant::now();
+/// let decay = BackwardDecay::new(g::Custom::new(|age: f64| 1.0 / (age + 1.0)));
+///
+/// assert_eq!(decay.weight(now, now), 1.0);
+/// assert_eq!(decay.weight(now - Duration::from_secs(1), now), 0.5);
+/// assert_eq!(decay.weighted_value((now - Duration::from_secs(3), 8.0), now), 2.0);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct BackwardDecay<F> {
+    f: F,
+}
+
+impl<F> BackwardDecay<F>
+where
+    F: Function,
+{
+    /// Create a new instance with a positive monotone non-increasing function f, where f(0) = 1.
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+
+    /// The function f for this decay model.
+    pub fn f(&self) -> &F {
+        &self.f
+    }
+
+    /// Given a positive monotone non-increasing function f,
+    /// the decayed weight of an item with arrival time ti measured at time t ≥ ti is given by w(i, t) = f(t − ti).
+    pub fn weight<I>(&self, item: I, timestamp: Instant) -> f64
+    where
+        I: Item,
+    {
+        self.f.invoke(-item.age(timestamp))
+    }
+
+    /// The value of this item multiplied by its weight.
+    pub fn weighted_value<I>(&self, item: I, timestamp: Instant) -> f64
+    where
+        I: Item,
+    {
+        self.weight(&item, timestamp) * item.value()
     }
 }
 
@@ -192,7 +376,7 @@ mod tests {
         let landmark = Instant::now();
         let tick = Duration::from_secs(1);
         let new_landmark = landmark + tick;
-        let stream = vec![5, 7, 3, 8, 4];
+        let stream = [5, 7, 3, 8, 4];
         let alpha = 1.0;
 
         let mut fd = ForwardDecay::new(landmark, g::Exponential::new(alpha));
@@ -215,6 +399,176 @@ mod tests {
         assert!(factors.iter().all(|d| *d < epsilon));
     }
 
+    #[test]
+    fn backward() {
+        let landmark = Instant::now();
+        let stream = [5, 7, 3, 8, 4];
+        let now = landmark + Duration::from_secs(10);
+        let alpha = 0.5;
+
+        let forward = ForwardDecay::new(landmark, g::Exponential::new(alpha));
+        let backward = BackwardDecay::new(g::Custom::new(|age: f64| (-alpha * age).exp()));
+        let epsilon = 0.0001;
+
+        for i in stream {
+            let item = landmark + Duration::from_secs(i);
+
+            assert!((forward.weight(item, now) - backward.weight(item, now)).abs() < epsilon);
+        }
+
+        let forward = ForwardDecay::new(landmark, g::Polynomial::new(2));
+        let backward = BackwardDecay::new(g::Custom::new(|age: f64| (age + 1.0).powi(-2)));
+
+        let forward_weights: Vec<f64> = stream.iter()
+            .map(|i| forward.weight(landmark + Duration::from_secs(*i), now))
+            .collect();
+        let backward_weights: Vec<f64> = stream.iter()
+            .map(|i| backward.weight(landmark + Duration::from_secs(*i), now))
+            .collect();
+
+        assert_eq!(forward_weights, vec![0.25, 0.49, 0.09, 0.64, 0.16]);
+        assert_eq!(backward_weights, vec![1.0 / 36.0, 1.0 / 16.0, 1.0 / 64.0, 1.0 / 9.0, 1.0 / 49.0]);
+    }
+
+    #[test]
+    fn weighted_sample() {
+        let landmark = Instant::now();
+        let stream: Vec<Instant> = [1, 2, 3, 4].iter().map(|i| landmark + Duration::from_secs(*i)).collect();
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(1));
+        let trials = 20_000;
+        let mut frequencies = [0usize; 4];
+
+        for _ in 0..trials {
+            let sample = fd.weighted_sample_without_replacement(&stream, 1);
+
+            assert_eq!(sample.len(), 1);
+
+            frequencies[sample[0]] += 1;
+        }
+
+        for (index, frequency) in frequencies.iter().enumerate() {
+            let expected = (index + 1) as f64 / 10.0;
+            let actual = *frequency as f64 / trials as f64;
+
+            assert!((expected - actual).abs() < 0.02, "index {index} expected {expected} but was {actual}");
+        }
+
+        let mut all = fd.weighted_sample_without_replacement(&stream, 10);
+        all.sort();
+
+        assert_eq!(all, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn weighted_sample_invalid_weights() {
+        let landmark = Instant::now();
+        // Under odd-degree polynomial decay, an item at the landmark has a weight of 0 and a pre-landmark item a negative weight.
+        let stream = [landmark - Duration::from_secs(1), landmark, landmark + Duration::from_secs(2)];
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(1));
+
+        for _ in 0..100 {
+            assert_eq!(fd.weighted_sample_without_replacement(&stream, 3), vec![2]);
+        }
+
+        let nan = ForwardDecay::new(landmark, g::Custom::new(|age: f64| if age > 1.0 { f64::NAN } else { 1.0 }));
+
+        for _ in 0..100 {
+            let mut sample = nan.weighted_sample_without_replacement(&stream, 3);
+            sample.sort();
+
+            assert_eq!(sample, vec![0, 1]);
+        }
+    }
+
+    #[test]
+    fn time_scale() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(600);
+        let minutes = ForwardDecay::with_time_scale(landmark, g::Exponential::new(0.3), 60.0);
+        let seconds = ForwardDecay::new(landmark, g::Exponential::new(0.3 / 60.0));
+        let epsilon = 1e-12;
+
+        for offset in [0, 30, 90, 300, 599] {
+            let item = landmark + Duration::from_secs(offset);
+
+            assert!((minutes.weight(item, now) - seconds.weight(item, now)).abs() < epsilon);
+        }
+
+        let mut rescaled = minutes;
+
+        assert_eq!(rescaled.set_landmark(landmark + Duration::from_secs(120)), 2.0);
+        assert_eq!(minutes.time_scale(), 60.0);
+        assert_eq!(seconds.time_scale(), 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_time_scale() {
+        ForwardDecay::with_time_scale(Instant::now(), (), 0.0);
+    }
+
+    #[test]
+    fn elapsed_landmark() {
+        let origin = landmark::origin();
+        let now = origin + Duration::from_secs(10);
+        let instant = ForwardDecay::new(origin + Duration::from_secs(2), g::Polynomial::new(2));
+        let mut elapsed = ForwardDecay::new(landmark::Elapsed(Duration::from_secs(2)), g::Polynomial::new(2));
+
+        assert_eq!(instant.landmark(), elapsed.landmark());
+
+        for offset in [3, 5, 7] {
+            let item = (origin + Duration::from_secs(offset), 1.0);
+
+            assert_eq!(instant.weight(item, now), elapsed.weight(item, now));
+        }
+
+        assert_eq!(elapsed.set_landmark(landmark::Elapsed(Duration::from_secs(4))), 2.0);
+        assert_eq!(elapsed.landmark(), origin + Duration::from_secs(4));
+    }
+
+    #[test]
+    fn landmark_age_at() {
+        let landmark = Instant::now();
+        let fd = ForwardDecay::new(landmark, g::Polynomial::new(2));
+
+        assert_eq!(fd.landmark_age_at(landmark - Duration::from_secs(2)), -2.0);
+        assert_eq!(fd.landmark_age_at(landmark), 0.0);
+        assert_eq!(fd.landmark_age_at(landmark + Duration::from_millis(1500)), 1.5);
+        assert!(!fd.is_after_landmark(landmark - Duration::from_secs(2)));
+        assert!(!fd.is_after_landmark(landmark));
+        assert!(fd.is_after_landmark(landmark + Duration::from_millis(1500)));
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono() {
+        use chrono::{TimeDelta, Utc};
+        use crate::aggregate::{Aggregator, BasicAggregator};
+
+        let landmark = Utc::now() - TimeDelta::seconds(10);
+        let now = landmark + TimeDelta::seconds(10);
+        let fd = ForwardDecay::from_date_time(landmark, g::Polynomial::new(2));
+        let stream = vec![
+            (landmark + TimeDelta::seconds(5), 4.0),
+            (landmark + TimeDelta::seconds(7), 8.0),
+            (landmark + TimeDelta::seconds(3), 3.0),
+            (landmark + TimeDelta::seconds(8), 6.0),
+            (landmark + TimeDelta::seconds(4), 4.0),
+        ];
+
+        let query = item::from_date_time(now);
+        let weights: Vec<f64> = stream.iter().map(|item| fd.weight(item, query)).collect();
+        let mut aggregator = BasicAggregator::new(fd);
+
+        for item in stream {
+            aggregator.update(item);
+        }
+
+        assert_eq!(weights, vec![0.25, 0.49, 0.09, 0.64, 0.16]);
+        assert_eq!(aggregator.sum(query), 9.67);
+        assert_eq!(fd.landmark_date_time(), landmark);
+    }
+
     #[test]
     fn age() {
         let landmark = Instant::now();
@@ -223,6 +577,10 @@ mod tests {
         assert_eq!(landmark.age(landmark), 0.0);
         assert_eq!((landmark + Duration::from_secs(5)).age(landmark), 5.0);
         assert_eq!((landmark + Duration::from_secs(10)).age(landmark), 10.0);
+        assert_eq!((landmark + Duration::from_millis(1)).age(landmark), 0.001);
+        assert_eq!((landmark + Duration::from_micros(1500)).age(landmark), 0.0015);
+        assert_eq!((landmark - Duration::from_micros(250)).age(landmark), -0.00025);
+        assert_eq!((landmark + Duration::from_nanos(1)).age(landmark), 1e-9);
     }
 }
 
@@ -0,0 +1,35 @@
+//! Floating-point operations used by the decay kernel.
+//! With the `libm` feature, they are computed by [libm](https://docs.rs/libm), a portable Rust implementation
+//! that gives the same results on every platform; otherwise they use the inherent methods of [f64],
+//! which call the platform's math library.
+//! The crate itself still requires `std`, i.e. for [Instant](std::time::Instant) timestamps.
+
+#[cfg(feature = "libm")]
+pub(crate) fn exp(x: f64) -> f64 {
+    libm::exp(x)
+}
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn exp(x: f64) -> f64 {
+    x.exp()
+}
+
+#[cfg(feature = "libm")]
+pub(crate) fn ln(x: f64) -> f64 {
+    libm::log(x)
+}
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn ln(x: f64) -> f64 {
+    x.ln()
+}
+
+#[cfg(feature = "libm")]
+pub(crate) fn powi(x: f64, n: i32) -> f64 {
+    libm::pow(x, n as f64)
+}
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn powi(x: f64, n: i32) -> f64 {
+    x.powi(n)
+}
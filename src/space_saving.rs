@@ -2,49 +2,205 @@
 //! The algorithm is adjusted according to support the [forward decay model](http://dimacs.rutgers.edu/~graham/pubs/papers/expdecay.pdf).
 
 use std::cmp::Ordering;
+use std::fmt::Debug;
 use std::collections::{BTreeSet, HashMap};
 use std::hash::Hash;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::ForwardDecay;
 use crate::g::{Exponential, Function};
+use crate::landmark::SyncLandmark;
+
+/// Residual counts at most this fraction of the weight of a single hit are treated as zero after [SpaceSaving::unhit_at].
+/// The threshold is relative to the hit's static weight, since static counts grow without bound under exponential decay,
+/// and is far above the rounding error of subtracting a few hits of similar weight.
+pub const ZERO_EPSILON: f64 = 1e-9;
+
+/// What a summary does with a counter whose count dropped to zero after [SpaceSaving::unhit_at].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ZeroPolicy {
+    /// Stop tracking the element, freeing its counter for other elements.
+    #[default]
+    Remove,
+    /// Keep tracking the element with a count of zero.
+    Retain,
+}
+
+/// A logical clock that advances by a fixed number of ticks on every hit, in place of the system clock.
+/// Tick n corresponds to the instant n seconds after the origin, so a decay function with the default time scale
+/// is evaluated over ticks, and whole ticks are represented exactly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ticks {
+    origin: Instant,
+    step: u64,
+    current: u64,
+}
+
+impl Ticks {
+    /// The number of ticks elapsed since the origin.
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    /// The number of ticks each hit advances the clock by.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// The instant corresponding to the given tick.
+    pub fn instant(&self, tick: u64) -> Instant {
+        self.origin + Duration::from_secs(tick)
+    }
+}
+
+/// How a [SpaceSaving] summary orders the counters of elements with equal counts and errors.
+/// Ties must only compare equal for the same element, so that distinct elements are kept apart in the ordered counters.
+pub trait TieBreak<E> {
+    /// Recorded for an element when it starts being tracked.
+    type Tag: Copy + Debug;
+
+    /// Assigns the tag of an element that starts being tracked.
+    fn tag(&mut self, element: &E) -> Self::Tag;
+
+    /// Orders two tracked elements with equal counts and errors.
+    fn compare(a: (&E, &Self::Tag), b: (&E, &Self::Tag)) -> Ordering;
+}
+
+/// Breaks ties by element, which requires ordered elements. See [BTreeSpaceSaving].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ByElement;
+
+impl<E> TieBreak<E> for ByElement where E: Ord {
+    type Tag = ();
+
+    fn tag(&mut self, _: &E) {}
+
+    fn compare(a: (&E, &()), b: (&E, &())) -> Ordering {
+        a.0.cmp(b.0)
+    }
+}
+
+/// Breaks ties by the order in which elements started being tracked, so elements are only hashed. See [HashSpaceSaving].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ByArrival {
+    sequence: u64,
+}
+
+impl<E> TieBreak<E> for ByArrival {
+    type Tag = u64;
+
+    fn tag(&mut self, _: &E) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+
+    fn compare(a: (&E, &u64), b: (&E, &u64)) -> Ordering {
+        a.1.cmp(b.1)
+    }
+}
 
 /// An aggregation computation that implements the [SpaceSaving[(http://dimacs.rutgers.edu/~graham/pubs/papers/expdecay.pdf) algorithm.
 /// Instead of a StreamSummary, this implementation uses a [BTreeSet] to maintain an ordered list of counters.
 /// The use of a [BTreeSet] avoids having to implement a [LinkedList](https://rust-unofficial.github.io/too-many-lists/) that allows shareable cursors.
+///
+/// Counters with equal counts and errors are ordered by the [TieBreak], see [BTreeSpaceSaving] and [HashSpaceSaving].
 #[derive(Debug)]
-pub struct BTreeSpaceSaving<E, G> {
+pub struct SpaceSaving<E, G, T> where T: TieBreak<E> {
     capacity: usize,
     decay: ForwardDecay<G>,
     hits: f64,
-    elements: HashMap<E, Count>,
-    counts: BTreeSet<Counter<E>>,
+    observed: u64,
+    minimum_support: f64,
+    evictions: u64,
+    latest: Option<Instant>,
+    auto_rescale: Option<f64>,
+    zero_policy: ZeroPolicy,
+    ticks: Option<Ticks>,
+    tie_break: T,
+    elements: HashMap<E, (Count, T::Tag)>,
+    counts: BTreeSet<Counter<E, T>>,
 }
 
-impl<E> BTreeSpaceSaving<E, Exponential>
+/// A [SpaceSaving] summary that breaks ties between counters with equal counts by element, which requires ordered elements.
+pub type BTreeSpaceSaving<E, G> = SpaceSaving<E, G, ByElement>;
+
+/// A [SpaceSaving] summary for elements that are only [Hash] and [Eq], not [Ord].
+///
+/// Ties between counters with equal counts are broken by a sequence number assigned when an element starts being tracked,
+/// i.e. by the order in which elements entered the summary, so the elements themselves are only hashed.
+///
+/// ## Example
+/// ```rust
+/// use std::time::{Duration, Instant};
+/// use fermentation::{ForwardDecay, g};
+/// use fermentation::space_saving::HashSpaceSaving;
+///
+/// let landmark = Instant::now();
+/// let now = landmark + Duration::from_secs(10);
+/// let mut ss = HashSpaceSaving::new(2, ForwardDecay::new(landmark, g::Polynomial::new(1)));
+///
+/// // Implements Hash and Eq, but not Ord.
+/// #[derive(Clone, PartialEq, Eq, Hash)]
+/// struct Key(&'static str);
+///
+/// ss.hit_at(Key("a"), landmark + Duration::from_secs(5));
+/// ss.hit_at(Key("b"), landmark + Duration::from_secs(8));
+///
+/// assert_eq!(ss.get(&Key("b"), now).map(|count| count.count()), Some(0.8));
+/// assert_eq!(ss.hits(now), 1.3);
+/// ```
+pub type HashSpaceSaving<E, G> = SpaceSaving<E, G, ByArrival>;
+
+impl<E, T> SpaceSaving<E, Exponential, T>
 where
-    E: Clone + Hash + Eq + Ord,
+    E: Clone + Hash + Eq,
+    T: TieBreak<E>,
 {
     pub fn update_landmark(&mut self, landmark: Instant) {
-        let age = self.decay.set_landmark(landmark);
-        let factor = self.decay.g().invoke(age);
+        self.rescale(landmark);
+    }
 
-        self.hits /= factor;
+    /// Automatically updates the landmark to the time of the latest hit
+    /// whenever the static weight of the latest hit, g(t − L), exceeds the given threshold,
+    /// preventing the counts from overflowing in long-running summaries.
+    ///
+    /// Rescaling resets the static weight of the latest hit to 1, so the landmark moves at most once every ln(threshold) / α,
+    /// and static counts and total hits stay within the threshold times their decayed values.
+    /// Bounding the weight rather than the static total hits avoids rescaling on every hit once the decayed total exceeds the threshold.
+    ///
+    /// Rescaling divides every count, error and the total hits by the same factor,
+    /// so the ordering of counters and the frequency guarantees are unaffected, aside from floating-point rounding.
+    ///
+    /// ## Panic
+    /// Panics when the threshold is not greater than 1.
+    pub fn set_auto_rescale(&mut self, threshold: f64) {
+        if threshold.is_nan() || threshold <= 1.0 {
+            panic!("threshold must be greater than 1, given {threshold}");
+        }
 
-        let counts = std::mem::take(&mut self.counts);
-        for mut counter in counts {
-            counter.count /= factor;
-            counter.error /= factor;
+        self.auto_rescale = Some(threshold);
+    }
+}
 
-            self.counts.insert(counter);
-        }
+impl<E, T> SyncLandmark for SpaceSaving<E, Exponential, T>
+where
+    E: Clone + Hash + Eq,
+    T: TieBreak<E>,
+{
+    fn landmark(&self) -> Instant {
+        self.decay.landmark()
+    }
+
+    fn update_landmark(&mut self, landmark: Instant) {
+        Self::update_landmark(self, landmark);
     }
 }
 
-impl<E, G> BTreeSpaceSaving<E, G>
+impl<E, G, T> SpaceSaving<E, G, T>
 where
-    E: Clone + Hash + Eq + Ord,
-    G: Function
+    E: Clone + Hash + Eq,
+    G: Function,
+    T: TieBreak<E> + Default,
 {
     /// Initializes a new aggregator with the given capacity and decay model.
     /// The error bound for the results are 1/capacity.
@@ -53,51 +209,212 @@ where
             capacity,
             decay,
             hits: 0.0,
+            observed: 0,
+            minimum_support: 0.0,
+            evictions: 0,
+            latest: None,
+            auto_rescale: None,
+            zero_policy: ZeroPolicy::default(),
+            ticks: None,
+            tie_break: T::default(),
             elements: Default::default(),
             counts: Default::default(),
         }
     }
+}
 
-    /// Increments the given element's counter by a single hit.
+impl<E, G, T> SpaceSaving<E, G, T>
+where
+    E: Clone + Hash + Eq,
+    G: Function,
+    T: TieBreak<E>,
+{
+    /// Increments the given element's counter by a single hit, counted now.
+    /// In tick mode, the clock first advances by a step. See [SpaceSaving::set_ticks].
     pub fn hit(&mut self, element: E) -> Count {
-        let now = Instant::now();
-        let weight = self.decay.static_weight(now);
+        if let Some(ticks) = self.ticks.as_mut() {
+            ticks.current += ticks.step;
+        }
 
-        self.hits += weight;
+        self.hit_at(element, self.now())
+    }
+
+    /// Increments the given element's counter by a single hit.
+    /// An alias for [SpaceSaving::hit], following common metrics conventions.
+    pub fn inc(&mut self, element: E) -> Count {
+        self.hit(element)
+    }
 
-        let count = self.elements.get(&element).copied();
-        let mut counter = Counter::new(element, count.unwrap_or_default());
+    /// Increments the given element's counter by a single hit at the given time, returning its updated static count.
+    ///
+    /// With a minimum support, a hit for an untracked element may count towards the total hits without the element being tracked,
+    /// in which case the returned count is zero. Use [SpaceSaving::try_hit_at] to tell rejected hits apart.
+    pub fn hit_at(&mut self, element: E, timestamp: Instant) -> Count {
+        self.try_hit_at(element, timestamp).unwrap_or_default()
+    }
+
+    /// Increments the given element's counter by a single hit at the given time, like [SpaceSaving::hit_at].
+    /// Returns the element's updated static count, or [None] when the hit counts towards the total hits
+    /// without the element being tracked, because it lacks the minimum support. See [SpaceSaving::set_minimum_support].
+    pub fn try_hit_at(&mut self, element: E, timestamp: Instant) -> Option<Count> {
+        let weight = self.decay.static_weight(timestamp);
+
+        self.hits += weight;
+        self.observed += 1;
+        self.latest = Some(self.latest.map_or(timestamp, |latest| latest.max(timestamp)));
 
-        match count {
+        let mut counter = match self.elements.get(&element).copied() {
             None => {
+                let mut count = Count::default();
+
                 if self.counts.len() >= self.capacity {
+                    if let Some(min) = self.counts.first() {
+                        if weight < self.minimum_support * min.count {
+                            return None;
+                        }
+                    }
+
                     if let Some(min) = self.counts.pop_first() {
                         self.elements.remove(&min.element);
-                        counter.count = min.count;
-                        counter.error = min.count;
+                        self.evictions += 1;
+                        count = Count { count: min.count, error: min.count };
                     }
                 }
+
+                let tag = self.tie_break.tag(&element);
+
+                Counter::new(element, count, tag)
             }
-            Some(_) => {
+            Some((count, tag)) => {
+                let counter = Counter::new(element, count, tag);
+
                 self.counts.remove(&counter);
+
+                counter
             }
-        }
+        };
 
         counter.count += weight;
 
-        let key = counter.key();
+        let mut key = counter.key();
 
         if let Some(value) = self.elements.get_mut(&counter.element) {
-            *value = key;
+            *value = (key, counter.tag);
         } else {
-            self.elements.insert(counter.element.clone(), key);
+            self.elements.insert(counter.element.clone(), (key, counter.tag));
         }
 
         self.counts.insert(counter);
 
-        key
+        // Only set for exponential decay, where rescaling preserves the results.
+        if let (Some(threshold), Some(latest)) = (self.auto_rescale, self.latest) {
+            if self.decay.normalizing_factor(latest) > threshold {
+                let factor = self.rescale(latest);
+
+                key.count /= factor;
+                key.error /= factor;
+            }
+        }
+
+        Some(key)
+    }
+
+    /// Removes a single hit, counted now, from the given element's counter. See [SpaceSaving::unhit_at].
+    /// In tick mode, the hit is removed at the current tick, without advancing the clock.
+    pub fn unhit(&mut self, element: &E) -> Option<Count> {
+        self.unhit_at(element, self.now())
+    }
+
+    /// Switches to tick mode, where [SpaceSaving::hit] advances a logical clock by the given number of ticks
+    /// instead of reading the system clock, so that counts are deterministic, i.e. for tests and benchmarks.
+    /// The clock starts at tick 0 at the current landmark, and tick n corresponds to the instant n seconds later,
+    /// so the decay is computed over ticks. Query at [SpaceSaving::now] for the counts at the current tick.
+    ///
+    /// Hits counted at explicit times with [SpaceSaving::hit_at] do not advance the clock.
+    pub fn set_ticks(&mut self, step: u64) {
+        self.ticks = Some(Ticks { origin: self.decay.landmark(), step, current: 0 });
+    }
+
+    /// The logical clock, in tick mode. See [SpaceSaving::set_ticks].
+    pub fn ticks(&self) -> Option<&Ticks> {
+        self.ticks.as_ref()
+    }
+
+    /// The current time: the instant of the current tick in tick mode, or the system time otherwise.
+    pub fn now(&self) -> Instant {
+        self.ticks.map_or_else(Instant::now, |ticks| ticks.instant(ticks.current))
+    }
+
+    /// Removes a single hit, previously counted at the given time, from the given element's counter and the total hits,
+    /// i.e. to retract an event that was recorded in error. Returns the element's updated count, if it is still tracked.
+    ///
+    /// The count and total hits are clamped at zero, so repeated decrements cannot drive them negative
+    /// through floating-point error, and the error of the counter is clamped to its count.
+    /// A count within [ZERO_EPSILON] of a hit's weight of zero is treated as zero and handled according to the [ZeroPolicy].
+    /// Retracting a hit for an untracked element only reduces the total hits.
+    pub fn unhit_at(&mut self, element: &E, timestamp: Instant) -> Option<Count> {
+        let weight = self.decay.static_weight(timestamp);
+
+        self.hits = (self.hits - weight).max(0.0);
+
+        let (count, tag) = self.elements.get(element).copied()?;
+        let mut counter = Counter::new(element.clone(), count, tag);
+
+        self.counts.remove(&counter);
+
+        counter.count -= weight;
+
+        if counter.count <= ZERO_EPSILON * weight {
+            counter.count = 0.0;
+
+            if self.zero_policy == ZeroPolicy::Remove {
+                self.elements.remove(element);
+                return None;
+            }
+        }
+
+        counter.error = counter.error.min(counter.count);
+
+        let key = counter.key();
+
+        self.elements.insert(counter.element.clone(), (key, counter.tag));
+        self.counts.insert(counter);
+
+        Some(key)
+    }
+
+    /// Sets what happens to a counter whose count drops to zero after [SpaceSaving::unhit_at].
+    pub fn set_zero_policy(&mut self, policy: ZeroPolicy) {
+        self.zero_policy = policy;
+    }
+
+    /// Sets the minimum support a new element needs to enter a full summary.
+    /// A hit for an untracked element only evicts the minimum counter when its weight is at least
+    /// the given multiple of the minimum count; otherwise the hit counts towards the total hits but is not tracked,
+    /// which [SpaceSaving::try_hit_at] reports as [None].
+    /// A value of 0 (the default) admits every element.
+    ///
+    /// Filtering reduces churn on streams with many one-off elements, at the cost of recall:
+    /// an element whose hits are spread out may never be admitted while the summary is full,
+    /// so the guarantee that every element with a frequency above 1/capacity is tracked no longer holds.
+    pub fn set_minimum_support(&mut self, support: f64) {
+        self.minimum_support = support;
+    }
+
+    /// Stops tracking every element, while keeping the total hits and the decay model (including its landmark).
+    /// Unlike creating a new summary, this restarts heavy hitter tracking within the ongoing decay window,
+    /// so frequencies of newly tracked elements remain relative to all hits seen so far.
+    pub fn clear_elements(&mut self) {
+        self.elements.clear();
+        self.counts.clear();
+    }
+
+    /// The number of times a tracked element was evicted to make room for a new one.
+    pub fn evictions(&self) -> u64 {
+        self.evictions
     }
 
+    #[must_use = "the top elements are computed on every call and should be used"]
     pub fn top(&self, k: usize) -> Result<Vec<&E>, Vec<&E>> {
         let mut top_k = Vec::with_capacity(k);
         let mut order = true;
@@ -132,6 +449,7 @@ where
         }
     }
 
+    #[must_use = "the frequent elements are computed on every call and should be used"]
     pub fn frequent(&self, phi: f64) -> Result<Vec<&E>, Vec<&E>> {
         let threshold = (phi * self.hits).ceil();
         let mut hitters = Vec::new();
@@ -154,32 +472,101 @@ where
         }
     }
 
+    /// The same elements as [SpaceSaving::frequent], each with its decayed count at the given time
+    /// and whether its own membership is guaranteed, i.e. its guaranteed count exceeds the threshold.
+    /// Unlike [SpaceSaving::frequent], a single element without a guarantee does not void the guarantee of the others.
+    #[must_use = "the frequent elements are computed on every call and should be used"]
+    pub fn frequent_with_counts(&self, phi: f64, timestamp: Instant) -> Vec<(&E, Count, bool)> {
+        let threshold = (phi * self.hits).ceil();
+        let factor = self.normalizing_factor(timestamp);
+
+        self.counts.iter().rev()
+            .take_while(|counter| counter.count > threshold)
+            .map(|counter| {
+                let count = Count { count: counter.count / factor, error: counter.error / factor };
+
+                (&counter.element, count, counter.guaranteed_count() >= threshold)
+            })
+            .collect()
+    }
+
+    /// The decayed count of the given element at the given time, if it is tracked.
+    ///
+    /// Querying at a time earlier than the latest hit would normalize by a factor smaller than the stored weights,
+    /// yielding counts above the number of hits. Instead, such queries are clamped to the time of the latest hit.
+    #[must_use]
     pub fn get(&self, element: &E, timestamp: Instant) -> Option<Count> {
-        let mut count = self.elements.get(element).copied()?;
-        count.count /= self.decay.normalizing_factor(timestamp);
-        count.error /= self.decay.normalizing_factor(timestamp);
+        let (mut count, _) = self.elements.get(element).copied()?;
+        let factor = self.normalizing_factor(timestamp);
+        count.count /= factor;
+        count.error /= factor;
         Some(count)
     }
 
+    /// The total decayed hits at the given time.
+    /// Queries earlier than the latest hit are clamped to the time of the latest hit.
+    #[must_use]
     pub fn hits(&self, timestamp: Instant) -> f64 {
-        self.hits / self.decay.normalizing_factor(timestamp)
+        self.hits / self.normalizing_factor(timestamp)
+    }
+
+    /// The raw number of hits since creation, independent of decay.
+    /// Unlike the decayed hits, this is unaffected by rescaling, [SpaceSaving::clear_elements]
+    /// and [SpaceSaving::unhit_at], which makes it useful for sanity checks on the input.
+    pub fn total_observed(&self) -> u64 {
+        self.observed
+    }
+
+    /// The maximum number of elements tracked at once.
+    pub fn capacity(&self) -> usize {
+        self.capacity
     }
 
     pub fn decay(&self) -> &ForwardDecay<G> {
         &self.decay
     }
+
+    /// Moves the landmark, dividing every static quantity by g of the landmark's age relative to the previous landmark.
+    /// This is only valid for exponential decay. Returns the factor.
+    fn rescale(&mut self, landmark: Instant) -> f64 {
+        let age = self.decay.set_landmark(landmark);
+        let factor = self.decay.g().invoke(age);
+
+        self.hits /= factor;
+
+        let counts = std::mem::take(&mut self.counts);
+        for mut counter in counts {
+            counter.count /= factor;
+            counter.error /= factor;
+
+            if let Some((count, _)) = self.elements.get_mut(&counter.element) {
+                *count = counter.key();
+            }
+
+            self.counts.insert(counter);
+        }
+
+        factor
+    }
+
+    fn normalizing_factor(&self, timestamp: Instant) -> f64 {
+        let timestamp = self.latest.map_or(timestamp, |latest| latest.max(timestamp));
+
+        self.decay.normalizing_factor(timestamp)
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct Counter<E> {
+#[derive(Debug)]
+struct Counter<E, T> where T: TieBreak<E> {
     count: f64,
     error: f64,
+    tag: T::Tag,
     element: E,
 }
 
-impl<E> Counter<E> {
-    fn new(element: E, count: Count) -> Self {
-        Self { count: count.count, error: count.error, element }
+impl<E, T> Counter<E, T> where T: TieBreak<E> {
+    fn new(element: E, count: Count, tag: T::Tag) -> Self {
+        Self { count: count.count, error: count.error, tag, element }
     }
 
     fn key(&self) -> Count {
@@ -191,21 +578,29 @@ impl<E> Counter<E> {
     }
 }
 
-impl<E> Ord for Counter<E> where E: Ord {
+impl<E, T> Ord for Counter<E, T> where T: TieBreak<E> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).expect("unable to compare counters")
+        // Ties are broken by the tie-break so distinct elements with equal counts are kept apart in the set.
+        (self.count, self.error).partial_cmp(&(other.count, other.error))
+            .expect("unable to compare counters")
+            .then_with(|| T::compare((&self.element, &self.tag), (&other.element, &other.tag)))
     }
 }
 
-impl<E> PartialOrd for Counter<E> where E: PartialOrd {
+impl<E, T> PartialOrd for Counter<E, T> where T: TieBreak<E> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        (self.count, self.error).partial_cmp(&(other.count, other.error))
+        Some(self.cmp(other))
     }
 }
 
+impl<E, T> PartialEq for Counter<E, T> where T: TieBreak<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
 
 // Counters will not contain NaN, so we can safely compare them for equality.
-impl<E> Eq for Counter<E> where E: Eq {
+impl<E, T> Eq for Counter<E, T> where T: TieBreak<E> {
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialOrd, PartialEq)]
@@ -213,3 +608,425 @@ pub struct Count {
     count: f64,
     error: f64,
 }
+
+impl Count {
+    /// The estimated count, which overestimates the true count by at most the error.
+    pub fn count(&self) -> f64 {
+        self.count
+    }
+
+    /// The maximum overestimation of the count.
+    pub fn error(&self) -> f64 {
+        self.error
+    }
+
+    /// The count the element is guaranteed to have, the estimate less the error.
+    pub fn guaranteed_count(&self) -> f64 {
+        self.count - self.error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::g;
+    use crate::testing::{self, Query, Rescalable};
+
+    use super::*;
+
+    impl<T> Rescalable for SpaceSaving<i64, Exponential, T> where T: TieBreak<i64> {
+        fn rescale(&mut self, landmark: Instant) {
+            self.update_landmark(landmark);
+        }
+    }
+
+    impl<T> Query for SpaceSaving<i64, Exponential, T> where T: TieBreak<i64> {
+        fn ingest(&mut self, timestamp: Instant, value: f64) {
+            self.hit_at(value as i64, timestamp);
+        }
+
+        fn query(&self, timestamp: Instant) -> Vec<f64> {
+            let mut elements: Vec<&i64> = self.elements.keys().collect();
+
+            elements.sort();
+
+            let counts = elements.into_iter()
+                .filter_map(|element| self.get(element, timestamp))
+                .flat_map(|count| [count.count, count.error]);
+
+            std::iter::once(self.hits(timestamp)).chain(counts).collect()
+        }
+    }
+
+    #[test]
+    fn rescale_invariant() {
+        testing::assert_rescale_invariant(|decay| BTreeSpaceSaving::new(8, decay));
+        testing::assert_rescale_invariant(|decay| HashSpaceSaving::new(8, decay));
+    }
+
+    #[test]
+    fn minimum_support() {
+        let landmark = Instant::now();
+        let mut unfiltered = BTreeSpaceSaving::new(4, ForwardDecay::new(landmark, ()));
+        let mut filtered = BTreeSpaceSaving::new(4, ForwardDecay::new(landmark, ()));
+
+        filtered.set_minimum_support(2.0);
+
+        for i in 0..1000u32 {
+            let timestamp = landmark + Duration::from_millis(i as u64);
+            let element = if i % 2 == 0 { 0 } else { i };
+
+            unfiltered.hit_at(element, timestamp);
+            filtered.hit_at(element, timestamp);
+        }
+
+        assert!(filtered.evictions() < unfiltered.evictions());
+        assert_eq!(filtered.evictions(), 0);
+        assert_eq!(filtered.hits(landmark), unfiltered.hits(landmark));
+        assert_eq!(filtered.top(1).unwrap_or_else(|top| top), vec![&0]);
+        assert_eq!(unfiltered.top(1).unwrap_or_else(|top| top), vec![&0]);
+
+        // A rejected hit is not tracked, so there is no count to return.
+        assert_eq!(filtered.try_hit_at(1000, landmark), None);
+        assert_eq!(filtered.hit_at(1000, landmark), Count::default());
+        assert_eq!(filtered.get(&1000, landmark), None);
+        assert!(unfiltered.try_hit_at(1000, landmark).is_some());
+    }
+
+    #[test]
+    fn query_before_latest_hit() {
+        let landmark = Instant::now();
+        let latest = landmark + Duration::from_secs(10);
+        let mut ss = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        ss.hit_at("a", landmark + Duration::from_secs(5));
+        ss.hit_at("a", latest);
+
+        let earlier = ss.get(&"a", landmark + Duration::from_secs(5));
+        let at_latest = ss.get(&"a", latest);
+
+        assert_eq!(earlier, at_latest);
+        assert_eq!(earlier.map(|count| count.count), Some(1.5));
+        assert_eq!(ss.hits(landmark + Duration::from_secs(1)), ss.hits(latest));
+        assert_eq!(ss.hits(landmark + Duration::from_secs(20)), 0.75);
+    }
+
+    #[test]
+    fn clear_elements() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut ss = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        ss.hit_at("a", landmark + Duration::from_secs(5));
+        ss.hit_at("b", landmark + Duration::from_secs(8));
+
+        let hits = ss.hits(now);
+
+        ss.clear_elements();
+
+        assert_eq!(ss.get(&"a", now), None);
+        assert_eq!(ss.get(&"b", now), None);
+        assert_eq!(ss.top(2), Err(vec![]));
+        assert_eq!(ss.hits(now), hits);
+        assert_eq!(ss.decay().landmark(), landmark);
+    }
+
+    #[test]
+    fn auto_rescale() {
+        let landmark = Instant::now();
+        let decay = ForwardDecay::new(landmark, g::Exponential::new(1.0));
+        let mut rescaled = BTreeSpaceSaving::new(3, decay);
+        let mut control = BTreeSpaceSaving::new(3, decay);
+
+        rescaled.set_auto_rescale(1000.0);
+
+        for i in 0..400u64 {
+            let timestamp = landmark + Duration::from_millis(i * 100);
+            let element = [0, 0, 0, 1, 1, 2, 3, 4][i as usize % 8];
+
+            rescaled.hit_at(element, timestamp);
+            control.hit_at(element, timestamp);
+        }
+
+        let now = landmark + Duration::from_secs(40);
+        let epsilon = 0.0001;
+
+        assert!(rescaled.decay().landmark() > landmark);
+        assert!(rescaled.hits < control.hits);
+        assert_eq!(rescaled.top(2), control.top(2));
+        assert!((rescaled.hits(now) - control.hits(now)).abs() < epsilon);
+
+        for element in [0, 1] {
+            let actual = rescaled.get(&element, now).unwrap_or_default();
+            let expected = control.get(&element, now).unwrap_or_default();
+
+            assert!((actual.count - expected.count).abs() < epsilon);
+        }
+    }
+
+    #[test]
+    fn auto_rescale_large_total() {
+        let landmark = Instant::now();
+        let mut ss = BTreeSpaceSaving::new(3, ForwardDecay::new(landmark, g::Exponential::new(0.001)));
+
+        ss.set_auto_rescale(2.0);
+
+        // The decayed total hits exceed the threshold long before the weight of a hit does at ~693 seconds.
+        for i in 0..100u64 {
+            ss.hit_at(i % 5, landmark + Duration::from_secs(i));
+        }
+
+        assert!(ss.hits > 2.0);
+        assert_eq!(ss.decay().landmark(), landmark);
+
+        ss.hit_at(0, landmark + Duration::from_secs(700));
+
+        assert_eq!(ss.decay().landmark(), landmark + Duration::from_secs(700));
+    }
+
+    #[test]
+    fn inc() {
+        let landmark = Instant::now();
+        let mut aliased = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, ()));
+        let mut expected = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, ()));
+
+        for element in ["a", "b", "a", "c", "a"] {
+            assert_eq!(aliased.inc(element), expected.hit(element));
+        }
+
+        assert_eq!(aliased.top(2), expected.top(2));
+        assert_eq!(aliased.get(&"a", landmark), expected.get(&"a", landmark));
+        assert_eq!(aliased.hits(landmark), expected.hits(landmark));
+    }
+
+    #[test]
+    fn unhit() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut ss = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, g::Exponential::new(0.3)));
+        let timestamps = [landmark + Duration::from_millis(1100), landmark + Duration::from_millis(2300)];
+
+        for timestamp in timestamps {
+            ss.hit_at("a", timestamp);
+        }
+
+        ss.hit_at("b", landmark);
+
+        assert!(ss.unhit_at(&"a", timestamps[1]).is_some());
+        assert_eq!(ss.unhit_at(&"a", timestamps[0]), None);
+        assert_eq!(ss.unhit_at(&"a", timestamps[0]), None);
+        assert_eq!(ss.get(&"a", now), None);
+        assert_eq!(ss.top(2).unwrap_or_else(|top| top), vec![&"b"]);
+
+        ss.unhit_at(&"b", landmark);
+        ss.unhit_at(&"b", landmark);
+
+        assert_eq!(ss.hits(now), 0.0);
+    }
+
+    #[test]
+    fn unhit_retain() {
+        let landmark = Instant::now();
+        let mut ss = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        ss.set_zero_policy(ZeroPolicy::Retain);
+        ss.hit_at("a", landmark + Duration::from_millis(300));
+        ss.hit_at("a", landmark + Duration::from_millis(700));
+
+        for _ in 0..3 {
+            ss.unhit_at(&"a", landmark + Duration::from_millis(700));
+        }
+
+        assert_eq!(ss.get(&"a", landmark + Duration::from_secs(1)), Some(Count::default()));
+        assert_eq!(ss.hits(landmark + Duration::from_secs(1)), 0.0);
+    }
+
+    #[test]
+    fn total_observed() {
+        let landmark = Instant::now();
+        let mut ss = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, g::Exponential::new(1.0)));
+
+        ss.set_auto_rescale(10.0);
+
+        for i in 0..100u64 {
+            ss.hit_at(i % 5, landmark + Duration::from_millis(i * 100));
+        }
+
+        ss.unhit_at(&0, landmark);
+        ss.clear_elements();
+
+        assert_eq!(ss.total_observed(), 100);
+        assert!(ss.decay().landmark() > landmark);
+    }
+
+    #[test]
+    fn frequent_with_counts() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(1);
+        let mut ss = BTreeSpaceSaving::new(3, ForwardDecay::new(landmark, ()));
+
+        for i in 0..20 {
+            ss.hit_at(i, landmark);
+        }
+
+        for _ in 0..50 {
+            ss.hit_at(100, landmark);
+        }
+
+        for _ in 0..30 {
+            ss.hit_at(200, landmark);
+        }
+
+        for i in 20..45 {
+            ss.hit_at(i, landmark);
+        }
+
+        // The last distinct element inherits the count of the evicted elements, so it exceeds the threshold without a guarantee.
+        let frequent = ss.frequent_with_counts(0.2, now);
+        let elements: Vec<_> = frequent.iter().map(|(element, _, _)| **element).collect();
+        let guarantees: Vec<_> = frequent.iter().map(|(_, _, guaranteed)| *guaranteed).collect();
+
+        assert_eq!(elements, vec![100, 200, 44]);
+        assert_eq!(guarantees, vec![true, true, false]);
+        assert_eq!(frequent[0].1, ss.get(&100, now).unwrap());
+        assert!(frequent[0].1.guaranteed_count() >= 50.0);
+        assert!(frequent[2].1.guaranteed_count() < 0.2 * ss.hits(now));
+        assert_eq!(ss.frequent(0.2), Err(vec![&100, &200, &44]));
+    }
+
+    #[test]
+    fn ticks() {
+        let landmark = Instant::now();
+        let mut ss = BTreeSpaceSaving::new(2, ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        ss.set_ticks(2);
+
+        for element in ["a", "b", "a", "b", "c"] {
+            ss.hit(element);
+        }
+
+        let now = ss.now();
+
+        // Hits at ticks 2, 4, 6, 8 and 10; "c" evicts "a" with a count of 8 and an error of 8.
+        assert_eq!(ss.ticks().map(Ticks::current), Some(10));
+        assert_eq!(now, landmark + Duration::from_secs(10));
+        assert_eq!(ss.hits(now), 3.0);
+        assert_eq!(ss.get(&"b", now), Some(Count { count: 1.2, error: 0.0 }));
+        assert_eq!(ss.get(&"c", now), Some(Count { count: 1.8, error: 0.8 }));
+        assert_eq!(ss.get(&"a", now), None);
+        assert_eq!(ss.top(2), Err(vec![&"c", &"b"]));
+
+        ss.unhit(&"b");
+
+        assert_eq!(ss.ticks().map(Ticks::current), Some(10));
+        assert_eq!(ss.get(&"b", now), Some(Count { count: 0.2, error: 0.0 }));
+    }
+
+    #[test]
+    fn hash_only() {
+        // Implements Hash and Eq, but not Ord.
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(10);
+        let mut ss = HashSpaceSaving::new(2, ForwardDecay::new(landmark, g::Polynomial::new(1)));
+        let (a, b, c) = (Point { x: 0, y: 0 }, Point { x: 1, y: 0 }, Point { x: 0, y: 1 });
+
+        ss.hit_at(a.clone(), landmark + Duration::from_secs(2));
+        ss.hit_at(b.clone(), landmark + Duration::from_secs(4));
+        ss.hit_at(a.clone(), landmark + Duration::from_secs(6));
+        ss.hit_at(c.clone(), landmark + Duration::from_secs(8));
+
+        // "c" evicts "b", the minimum counter, inheriting its count as error.
+        assert_eq!(ss.get(&a, now), Some(Count { count: 0.8, error: 0.0 }));
+        assert_eq!(ss.get(&c, now), Some(Count { count: 1.2, error: 0.4 }));
+        assert_eq!(ss.get(&b, now), None);
+        assert_eq!(ss.evictions(), 1);
+        assert_eq!(ss.hits(now), 2.0);
+        assert_eq!(ss.top(2), Err(vec![&c, &a]));
+        assert_eq!(ss.frequent(0.3), Ok(vec![&c, &a]));
+        assert_eq!(ss.total_observed(), 4);
+
+        // Ties are broken by the order in which elements entered the summary.
+        let mut ties = HashSpaceSaving::new(3, ForwardDecay::new(landmark, ()));
+
+        ties.hit_at(b.clone(), landmark);
+        ties.hit_at(a.clone(), landmark);
+
+        assert_eq!(ties.top(2), Err(vec![&a, &b]));
+    }
+
+    #[test]
+    fn hash_only_update_landmark() {
+        let landmark = Instant::now();
+        let now = landmark + Duration::from_secs(100);
+        let mut ss = HashSpaceSaving::new(4, ForwardDecay::new(landmark, g::Exponential::new(0.1)));
+
+        for i in 0..100u64 {
+            ss.hit_at(i % 7, landmark + Duration::from_secs(i));
+        }
+
+        let before: Vec<_> = (0..7).map(|element| ss.get(&element, now)).collect();
+        let top: Vec<u64> = ss.top(4).unwrap_or_else(|top| top).into_iter().copied().collect();
+
+        ss.update_landmark(landmark + Duration::from_secs(50));
+
+        let epsilon = 1e-9;
+
+        for (element, count) in before.into_iter().enumerate() {
+            let after = ss.get(&(element as u64), now);
+
+            assert_eq!(count.is_some(), after.is_some());
+
+            if let (Some(count), Some(after)) = (count, after) {
+                assert!((count.count() - after.count()).abs() < epsilon);
+            }
+        }
+
+        assert_eq!(ss.top(4).unwrap_or_else(|top| top).into_iter().copied().collect::<Vec<_>>(), top);
+    }
+
+    #[test]
+    fn equal_counts() {
+        let landmark = Instant::now();
+        let mut ss = BTreeSpaceSaving::new(3, ForwardDecay::new(landmark, ()));
+
+        for element in ["a", "b", "c"] {
+            ss.hit_at(element, landmark);
+        }
+
+        // Distinct elements with equal counts must each keep a counter, ordered by element.
+        assert_eq!(ss.top(3), Err(vec![&"c", &"b", &"a"]));
+    }
+
+    #[test]
+    fn hash_only_shared_features() {
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        struct Key(u32);
+
+        let landmark = Instant::now();
+        let mut ss = HashSpaceSaving::new(2, ForwardDecay::new(landmark, g::Polynomial::new(1)));
+
+        ss.set_ticks(2);
+        ss.set_minimum_support(2.0);
+
+        for element in [0, 1, 0, 1, 2] {
+            ss.hit(Key(element));
+        }
+
+        let now = ss.now();
+
+        // "2" lacks the minimum support to evict a counter of the full summary.
+        assert_eq!(now, landmark + Duration::from_secs(10));
+        assert_eq!(ss.get(&Key(2), now), None);
+        assert_eq!(ss.evictions(), 0);
+        assert_eq!(ss.hits(now), 3.0);
+        assert_eq!(ss.unhit(&Key(1)), Some(Count { count: 2.0, error: 0.0 }));
+        assert_eq!(ss.get(&Key(1), now), Some(Count { count: 0.2, error: 0.0 }));
+    }
+}
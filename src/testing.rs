@@ -0,0 +1,58 @@
+//! Shared harnesses for properties that every aggregator of a kind must uphold.
+
+use std::time::{Duration, Instant};
+
+use crate::ForwardDecay;
+use crate::g::Exponential;
+
+/// An aggregator whose landmark can be moved without discarding its aggregates.
+pub(crate) trait Rescalable {
+    fn rescale(&mut self, landmark: Instant);
+}
+
+/// An aggregator that ingests timestamped values and reports its decayed outputs.
+pub(crate) trait Query {
+    fn ingest(&mut self, timestamp: Instant, value: f64);
+
+    /// Every decayed output of the aggregator at the given time, in a stable order.
+    fn query(&self, timestamp: Instant) -> Vec<f64>;
+}
+
+/// Verifies that rescaling an exponentially decayed aggregator to a new landmark partway through a standard stream
+/// matches a control aggregator that used the new landmark from the start.
+pub(crate) fn assert_rescale_invariant<A>(new: impl Fn(ForwardDecay<Exponential>) -> A)
+where
+    A: Rescalable + Query,
+{
+    let landmark = Instant::now();
+    let new_landmark = landmark + Duration::from_secs(2);
+    let stream = [(5, -4.0), (7, 8.0), (3, 3.0), (8, -6.0), (4, 4.0), (9, 8.0), (6, 3.0)];
+    let g = Exponential::new(0.2);
+    let mut rescaled = new(ForwardDecay::new(landmark, g));
+    let mut control = new(ForwardDecay::new(new_landmark, g));
+
+    for (index, (offset, value)) in stream.into_iter().enumerate() {
+        if index == stream.len() / 2 {
+            rescaled.rescale(new_landmark);
+        }
+
+        let timestamp = landmark + Duration::from_secs(offset);
+
+        rescaled.ingest(timestamp, value);
+        control.ingest(timestamp, value);
+    }
+
+    let epsilon = 0.0001;
+
+    for offset in [10, 20, 60] {
+        let now = landmark + Duration::from_secs(offset);
+        let actual = rescaled.query(now);
+        let expected = control.query(now);
+
+        assert_eq!(actual.len(), expected.len());
+
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            assert!((actual - expected).abs() <= epsilon * expected.abs().max(1.0), "expected {expected}, got {actual} at {offset} seconds");
+        }
+    }
+}